@@ -13,58 +13,90 @@ criterion_group!(benches, functions);
 
 fn functions(c: &mut Criterion) {
     c.bench_function("Teams", teams);
+    c.bench_function("Bytes Teams", bytes_teams);
     c.bench_function("Write Teams", std_write::teams);
     c.bench_function("Formatter Teams", fmt::teams);
     c.bench_function("Big table", |b| big_table(b, &100));
+    c.bench_function("Bytes Big table", |b| bytes_big_table(b, &100));
     c.bench_function("Write Big table", |b| std_write::big_table(b, &100));
     c.bench_function("Formatter Big table", |b| fmt::big_table(b, &100));
 }
 
 fn big_table(b: &mut criterion::Bencher, size: &usize) {
-    let mut table = Vec::with_capacity(*size);
-    for _ in 0..*size {
-        let mut inner = Vec::with_capacity(*size);
-        for i in 0..*size {
-            inner.push(i);
-        }
-        table.push(inner);
-    }
-    let ctx = BigTable { table };
+    let ctx = BigTable::new(*size);
     b.iter(|| ctx.call().unwrap());
 }
 
+fn bytes_big_table(b: &mut criterion::Bencher, size: &usize) {
+    let ctx = BigTable::new(*size);
+    b.iter(|| {
+        let mut buf = Vec::with_capacity(BigTable::size_hint());
+        ctx.render_into_vec(&mut buf).unwrap();
+        buf
+    });
+}
+
 #[derive(Template)]
 #[template(path = "big-table.html")]
 struct BigTable {
     table: Vec<Vec<usize>>,
 }
 
-fn teams(b: &mut criterion::Bencher) {
-    let teams = Teams {
-        year: 2015,
-        teams: vec![
-            Team {
-                name: "Jiangsu".into(),
+impl BigTable {
+    fn new(size: usize) -> BigTable {
+        let mut table = Vec::with_capacity(size);
+        for _ in 0..size {
+            let mut inner = Vec::with_capacity(size);
+            for i in 0..size {
+                inner.push(i);
+            }
+            table.push(inner);
+        }
+        BigTable { table }
+    }
+}
 
-                score: 43,
-            },
-            Team {
-                name: "Beijing".into(),
-                score: 27,
-            },
-            Team {
-                name: "Guangzhou".into(),
-                score: 22,
-            },
-            Team {
-                name: "Shandong".into(),
-                score: 12,
-            },
-        ],
-    };
+fn teams(b: &mut criterion::Bencher) {
+    let teams = Teams::new();
     b.iter(|| teams.call().unwrap());
 }
 
+fn bytes_teams(b: &mut criterion::Bencher) {
+    let teams = Teams::new();
+    b.iter(|| {
+        let mut buf = Vec::with_capacity(Teams::size_hint());
+        teams.render_into_vec(&mut buf).unwrap();
+        buf
+    });
+}
+
+impl Teams {
+    fn new() -> Teams {
+        Teams {
+            year: 2015,
+            teams: vec![
+                Team {
+                    name: "Jiangsu".into(),
+
+                    score: 43,
+                },
+                Team {
+                    name: "Beijing".into(),
+                    score: 27,
+                },
+                Team {
+                    name: "Guangzhou".into(),
+                    score: 22,
+                },
+                Team {
+                    name: "Shandong".into(),
+                    score: 12,
+                },
+            ],
+        }
+    }
+}
+
 #[derive(Template)]
 #[template(path = "teams.html")]
 struct Teams {
@@ -1,11 +1,7 @@
 #![cfg(feature = "actix")]
-extern crate actix_web;
-extern crate bytes;
-
-use actix_web::http::header::CONTENT_TYPE;
-use actix_web::test;
-use actix_web::HttpMessage;
-use bytes::Bytes;
+use actix_web::http::header::{ACCEPT, CONTENT_TYPE};
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
 use wearte::Template;
 
 #[derive(Template)]
@@ -14,15 +10,39 @@ struct HelloTemplate<'a> {
     name: &'a str,
 }
 
-#[test]
-fn test_actix_web() {
-    let mut srv = test::TestServer::new(|app| app.handler(|_| HelloTemplate { name: "world" }));
+async fn hello() -> HelloTemplate<'static> {
+    HelloTemplate { name: "world" }
+}
+
+#[actix_web::test]
+async fn test_actix_web() {
+    let app = test::init_service(App::new().route("/", web::get().to(hello))).await;
 
-    let request = srv.get().finish().unwrap();
-    let response = srv.execute(request.send()).unwrap();
+    let request = test::TestRequest::get().uri("/").to_request();
+    let response = test::call_service(&app, request).await;
     assert!(response.status().is_success());
     assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), "text/html");
 
-    let bytes = srv.execute(response.body()).unwrap();
-    assert_eq!(bytes, Bytes::from_static("Hello, world!".as_ref()));
+    let bytes = test::read_body(response).await;
+    assert_eq!(&bytes[..], b"Hello, world!");
+}
+
+#[actix_web::test]
+async fn test_negotiation() {
+    let app = test::init_service(App::new().route("/", web::get().to(hello))).await;
+
+    for (accept, status) in &[
+        ("text/html", StatusCode::OK),
+        ("application/json;q=0.9, text/*;q=0.5", StatusCode::OK),
+        ("*/*", StatusCode::OK),
+        ("application/json", StatusCode::NOT_ACCEPTABLE),
+        ("text/*, text/html;q=0", StatusCode::NOT_ACCEPTABLE),
+    ] {
+        let request = test::TestRequest::get()
+            .uri("/")
+            .insert_header((ACCEPT, *accept))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), *status, "{}", accept);
+    }
 }
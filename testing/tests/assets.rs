@@ -0,0 +1,21 @@
+use wearte::Template;
+
+#[derive(Template)]
+#[template(
+    src = r#"<link href="{{ asset "app.css" }}" integrity="{{ integrity "app.css" }}">
+<script src="{{ asset "/js/app.min.js" }}"></script>"#,
+    ext = "html"
+)]
+struct Assets;
+
+#[test]
+fn test_assets() {
+    let t = Assets;
+    assert_eq!(
+        t.call().unwrap(),
+        "<link href=\"/static/app.9767e91e.css\" \
+         integrity=\"sha384-AfvuHvJDW/YFp/cnN1kkrn86hCkG+0GL/j5XHj5y7KAoLOcK+iPu2eVrHptLLv9m\">\n\
+         <script src=\"/static/js/app.min.2bf8b125.js\"></script>"
+    );
+    assert_eq!(Assets::size_hint(), t.call().unwrap().len());
+}
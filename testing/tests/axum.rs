@@ -0,0 +1,87 @@
+#![cfg(feature = "with-axum")]
+use std::fmt;
+
+use axum::body::{to_bytes, Body};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{Request, StatusCode};
+use axum::routing::get;
+use axum::Router;
+use tower::ServiceExt;
+use wearte::Template;
+
+#[derive(Template)]
+#[template(path = "hello.html")]
+struct HelloTemplate<'a> {
+    name: &'a str,
+}
+
+struct Broken;
+
+impl fmt::Display for Broken {
+    fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+        Err(fmt::Error)
+    }
+}
+
+#[derive(Template)]
+#[template(src = "{{ broken }}", ext = "txt")]
+struct BrokenTemplate {
+    broken: Broken,
+}
+
+// hand written, the derive would reject the mime type
+struct BadMime;
+
+impl fmt::Display for BadMime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("bad")
+    }
+}
+
+impl Template for BadMime {
+    fn mime() -> &'static str {
+        "text/html\r\n"
+    }
+
+    fn size_hint() -> usize {
+        3
+    }
+}
+
+fn app() -> Router {
+    Router::new()
+        .route("/", get(|| async { HelloTemplate { name: "world" } }))
+        .route(
+            "/broken",
+            get(|| async { BrokenTemplate { broken: Broken } }),
+        )
+        .route(
+            "/mime",
+            get(|| async { wearte::axum::into_response(&BadMime) }),
+        )
+}
+
+#[tokio::test]
+async fn test_axum() {
+    let request = Request::get("/").body(Body::empty()).unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), "text/html");
+
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&bytes[..], b"Hello, world!");
+}
+
+#[tokio::test]
+async fn test_error() {
+    let request = Request::get("/broken").body(Body::empty()).unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn test_bad_mime() {
+    let request = Request::get("/mime").body(Body::empty()).unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
@@ -0,0 +1,71 @@
+use wearte::{Html, Template};
+
+fn render<T: Template>(t: &T) -> String {
+    let mut buf = vec![];
+    t.render_into_vec(&mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[derive(Template)]
+#[template(path = "teams.html")]
+struct Teams {
+    year: u16,
+    teams: Vec<Team>,
+}
+
+struct Team {
+    name: String,
+    score: u8,
+}
+
+#[test]
+fn test_teams() {
+    let t = Teams {
+        year: 2015,
+        teams: vec![
+            Team {
+                name: "<Jiangsu>".into(),
+                score: 43,
+            },
+            Team {
+                name: "Beijing".into(),
+                score: 27,
+            },
+        ],
+    };
+    assert_eq!(t.call().unwrap(), render(&t));
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{ let a = name.len() }}{{ name }}{{{ name }}}{{ a }}{{{ a }}}{{ html }}{{ c }}",
+    ext = "html"
+)]
+struct ExprTemplate<'a> {
+    name: &'a str,
+    html: Html,
+    c: char,
+}
+
+#[test]
+fn test_expr() {
+    let t = ExprTemplate {
+        name: "<'a'/>",
+        html: Html::trusted("<br>".into()),
+        c: '&',
+    };
+    assert_eq!("&lt;&#x27;a&#x27;&#x2f;&gt;<'a'/>66<br>&amp;", render(&t));
+    assert_eq!(t.call().unwrap(), render(&t));
+}
+
+#[derive(Template)]
+#[template(src = "{\"a\": \"{{ a }}\"}", ext = "json", escape = "json")]
+struct JsonTemplate<'a> {
+    a: &'a str,
+}
+
+#[test]
+fn test_json() {
+    let t = JsonTemplate { a: "\"\n" };
+    assert_eq!("{\"a\": \"\\\"\\n\"}", render(&t));
+}
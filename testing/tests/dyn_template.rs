@@ -0,0 +1,62 @@
+use std::fmt::Write;
+
+use wearte::Template;
+
+#[derive(Template)]
+#[template(src = "<p>{{ text }}</p>", ext = "html")]
+struct Paragraph<'a> {
+    text: &'a str,
+}
+
+#[derive(Template)]
+#[template(src = "{{ count }} items", ext = "txt")]
+struct Counter {
+    count: usize,
+}
+
+#[test]
+fn test_dyn() {
+    let widgets: Vec<Box<dyn Template>> = vec![
+        Box::new(Paragraph { text: "<hi>" }),
+        Box::new(Counter { count: 3 }),
+    ];
+
+    let mut out = String::new();
+    for w in &widgets {
+        writeln!(out, "{}: {}", w.dyn_mime(), w.call().unwrap()).unwrap();
+        assert!(0 < w.dyn_size_hint());
+    }
+    assert_eq!(out, "text/html: <p>&lt;hi&gt;</p>\ntext/plain: 3 items\n");
+
+    let mut buf = vec![];
+    widgets[1].call_into_io(&mut buf).unwrap();
+    assert_eq!(buf, b"3 items");
+    assert_eq!(Paragraph::mime(), widgets[0].dyn_mime());
+}
+
+// only the static methods, `dyn_mime` and `dyn_size_hint` have default bodies
+struct Manual;
+
+impl std::fmt::Display for Manual {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("manual")
+    }
+}
+
+impl Template for Manual {
+    fn mime() -> &'static str {
+        "text/plain"
+    }
+
+    fn size_hint() -> usize {
+        6
+    }
+}
+
+#[test]
+fn test_dyn_defaults() {
+    let w: &dyn Template = &Manual;
+    assert_eq!(w.dyn_mime(), "text/plain");
+    assert_eq!(w.dyn_size_hint(), 6);
+    assert_eq!(w.call().unwrap(), "manual");
+}
@@ -0,0 +1,24 @@
+use wearte::Template;
+
+#[derive(Template)]
+#[template(
+    src = r#"<i>{{> raw "icons/check.svg" ~}}</i>
+<pre>{{> raw "icons/critical.css" escape ~}}</pre>
+<img src="data:image/svg+xml;base64,{{> raw "icons/check.svg" base64 }}">"#,
+    ext = "html"
+)]
+struct Embed;
+
+#[test]
+fn test_embed() {
+    let t = Embed;
+    assert_eq!(
+        t.call().unwrap(),
+        "<i><svg viewBox=\"0 0 8 8\"><path d=\"M0 4l3 3 5-7\"/><desc>{{ not parsed }}</desc></svg></i>\n\
+         <pre>a::before { content: &quot;{{&quot;; }</pre>\n\
+         <img src=\"data:image/svg+xml;base64,PHN2ZyB2aWV3Qm94PSIwIDAgOCA4Ij48cGF0aCBkPSJNMCA0bDMgMyA1LTciLz48ZGVzYz57eyBub3QgcGFyc2VkIH19PC9kZXNjPjwvc3ZnPgo=\">"
+    );
+    let mut buf = vec![];
+    t.render_into(&mut buf).unwrap();
+    assert_eq!(buf, t.call().unwrap().as_bytes());
+}
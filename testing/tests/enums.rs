@@ -0,0 +1,95 @@
+use wearte::Template;
+
+#[derive(Template)]
+#[template(src = "<h1>Hello, {{ name }}!</h1>", ext = "html")]
+struct Home {
+    name: String,
+}
+
+#[derive(Template)]
+enum Page {
+    Home(Home),
+    #[template(
+        src = "{{#if code == 404 }}Not found{{else}}Error {{ code }}{{/if}}: {{ msg }}",
+        ext = "html"
+    )]
+    Error {
+        code: u16,
+        msg: String,
+    },
+    #[template(src = "<p>{{ self }}</p>", ext = "html")]
+    Text(String),
+    #[template(src = "Empty", ext = "html")]
+    Empty,
+}
+
+#[test]
+fn test_enum() {
+    let home = Page::Home(Home {
+        name: "<world>".into(),
+    });
+    assert_eq!(home.call().unwrap(), "<h1>Hello, &lt;world&gt;!</h1>");
+
+    let error = Page::Error {
+        code: 404,
+        msg: "<gone>".into(),
+    };
+    assert_eq!(error.call().unwrap(), "Not found: &lt;gone&gt;");
+    let error = Page::Error {
+        code: 500,
+        msg: "oops".into(),
+    };
+    assert_eq!(error.to_string(), "Error 500: oops");
+
+    let text = Page::Text("a & b".into());
+    assert_eq!(text.call().unwrap(), "<p>a &amp; b</p>");
+    assert_eq!(Page::Empty.call().unwrap(), "Empty");
+
+    let mut buf = vec![];
+    error.render_into(&mut buf).unwrap();
+    assert_eq!(buf, b"Error 500: oops");
+}
+
+#[test]
+fn test_enum_hints() {
+    assert_eq!(Page::mime(), "text/html");
+    assert_eq!(Page::Empty.dyn_mime(), "text/html");
+
+    assert!(Home::size_hint() <= Page::size_hint());
+    assert_eq!(Page::Empty.dyn_size_hint(), 5);
+    assert_eq!(
+        Page::Home(Home { name: "".into() }).dyn_size_hint(),
+        Home::size_hint()
+    );
+}
+
+// the same file read with different whitespace modes
+#[derive(Template)]
+enum List<'a> {
+    #[template(path = "items.txt", whitespace = "standalone")]
+    Standalone { items: &'a [&'a str] },
+    #[template(path = "items.txt", whitespace = "preserve")]
+    Preserve { items: &'a [&'a str] },
+}
+
+#[test]
+fn test_enum_whitespace() {
+    let items = &["a", "b"];
+    assert_eq!(List::Standalone { items }.call().unwrap(), "- a\n- b\n");
+    assert_eq!(List::Preserve { items }.call().unwrap(), "\n- a\n\n- b\n");
+}
+
+// delegated variants agree on the mime type at compile time
+#[derive(Template)]
+enum Layout {
+    Page(Page),
+    #[template(src = "<hr>", ext = "html")]
+    Rule,
+}
+
+#[test]
+fn test_enum_delegated() {
+    assert_eq!(Layout::mime(), "text/html");
+    assert_eq!(Layout::Page(Page::Empty).call().unwrap(), "Empty");
+    assert_eq!(Layout::Rule.call().unwrap(), "<hr>");
+}
@@ -0,0 +1,99 @@
+use std::fmt::{self, Write};
+
+use wearte::escapers::Escaper;
+use wearte::Template;
+
+#[derive(Template)]
+#[template(src = "{\"name\": \"{{ name }}\"}", ext = "json", escape = "json")]
+struct JsonTemplate<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_json() {
+    let t = JsonTemplate {
+        name: "\"foo\"\n\\<bar>",
+    };
+    assert_eq!(r#"{"name": "\"foo\"\n\\<bar>"}"#, t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(src = "<a b='{{ name }}'/>", ext = "xml", escape = "xml")]
+struct XmlTemplate<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_xml() {
+    let t = XmlTemplate { name: "'&\"" };
+    assert_eq!("<a b='&apos;&amp;&quot;'/>", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(src = "{{ name }}", ext = "html", escape = "none")]
+struct NoneTemplate<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_none() {
+    let t = NoneTemplate { name: "<&>" };
+    assert_eq!("<&>", t.call().unwrap());
+}
+
+struct Upper;
+
+impl Escaper for Upper {
+    fn escape<W: Write + ?Sized>(s: &str, writer: &mut W) -> fmt::Result {
+        writer.write_str(&s.to_uppercase())
+    }
+}
+
+#[derive(Template)]
+#[template(src = "{{ name }} {{{ name }}}", ext = "txt", escape = "Upper")]
+struct CustomTemplate<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_custom() {
+    let t = CustomTemplate { name: "foo" };
+    assert_eq!("FOO foo", t.call().unwrap());
+}
+
+// hand written, its MIME type is only known when rendering
+struct Child;
+
+impl fmt::Display for Child {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("child")
+    }
+}
+
+impl Template for Child {
+    fn mime() -> &'static str {
+        "text/plain"
+    }
+
+    fn size_hint() -> usize {
+        5
+    }
+}
+
+#[derive(Template)]
+#[template(src = "{{ name }} {{ child }}", ext = "txt", escape = "Upper")]
+struct CustomParent<'a> {
+    name: &'a str,
+    child: Child,
+}
+
+#[test]
+fn test_custom_rejects_children() {
+    // `Upper` doesn't set `MIMES`, it accepts no nested template
+    let t = CustomParent {
+        name: "foo",
+        child: Child,
+    };
+    assert!(t.call().is_err());
+    assert!(t.render_into_vec(&mut vec![]).is_err());
+}
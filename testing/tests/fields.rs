@@ -0,0 +1,64 @@
+use std::ops::Deref;
+
+use wearte::Template;
+
+#[derive(Template)]
+#[template(
+    src = "{{ title }} {{ self.count }} {{ format!(\"{}\", self.flag) }}",
+    ext = "txt",
+    check_fields = true,
+    warn_unused = true
+)]
+struct Fields {
+    title: &'static str,
+    count: usize,
+    flag: bool,
+}
+
+#[derive(Template)]
+enum Status {
+    #[template(src = "{{ self.code }} {{ reason }}", ext = "txt")]
+    Error { code: u16, reason: &'static str },
+}
+
+struct Base {
+    title: &'static str,
+}
+
+// `title` comes through `Deref`, the fields aren't checked by default
+#[derive(Template)]
+#[template(src = "{{ title }}: {{ body }}", ext = "txt")]
+struct Page {
+    base: Base,
+    body: &'static str,
+}
+
+impl Deref for Page {
+    type Target = Base;
+
+    fn deref(&self) -> &Base {
+        &self.base
+    }
+}
+
+#[test]
+fn test_fields() {
+    let t = Fields {
+        title: "a",
+        count: 2,
+        flag: true,
+    };
+    assert_eq!(t.call().unwrap(), "a 2 true");
+
+    let s = Status::Error {
+        code: 404,
+        reason: "Not Found",
+    };
+    assert_eq!(s.call().unwrap(), "404 Not Found");
+
+    let p = Page {
+        base: Base { title: "Home" },
+        body: "welcome",
+    };
+    assert_eq!(p.call().unwrap(), "Home: welcome");
+}
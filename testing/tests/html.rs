@@ -0,0 +1,54 @@
+use wearte::{html, Html, Template};
+
+fn icon(name: &str) -> Html {
+    html!("<i class=\"icon-", name, "\"></i>")
+}
+
+#[derive(Template)]
+#[template(src = "{{ icon(name) }}{{ name }}", ext = "html")]
+struct IconTemplate<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_helper() {
+    let t = IconTemplate { name: "<x>" };
+    assert_eq!(
+        "<i class=\"icon-&lt;x&gt;\"></i>&lt;x&gt;",
+        t.call().unwrap()
+    );
+}
+
+#[derive(Template)]
+#[template(src = "<div>{{ inner }}</div>", ext = "html")]
+struct OuterTemplate {
+    inner: Html,
+}
+
+#[test]
+fn test_nested() {
+    let inner = IconTemplate { name: "a" }.as_html().unwrap();
+    let t = OuterTemplate { inner };
+    assert_eq!(
+        "<div><i class=\"icon-a\"></i>a</div>",
+        t.call().unwrap()
+    );
+}
+
+#[derive(Template)]
+#[template(src = "{\"icon\": \"{{ icon }}\"}", ext = "json", escape = "json")]
+struct JsonTemplate {
+    icon: Html,
+}
+
+#[test]
+fn test_json() {
+    let t = JsonTemplate { icon: icon("a") };
+    assert_eq!(
+        "{\"icon\": \"<i class=\\\"icon-a\\\"></i>\"}",
+        t.call().unwrap()
+    );
+    let mut buf = vec![];
+    t.render_into(&mut buf).unwrap();
+    assert_eq!(buf, t.call().unwrap().as_bytes());
+}
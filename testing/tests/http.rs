@@ -0,0 +1,71 @@
+#![cfg(feature = "with-http")]
+use std::fmt;
+
+use wearte::http::{response, response_to, HeaderMap, Options, StatusCode};
+use wearte::Template;
+
+#[derive(Template)]
+#[template(path = "hello.html")]
+struct HelloTemplate<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_response() {
+    let res = response(&HelloTemplate { name: "world" });
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["content-type"], "text/html; charset=utf-8");
+    assert!(res.headers().get("etag").is_none());
+    assert_eq!(&res.body()[..], b"Hello, world!");
+}
+
+#[test]
+fn test_etag() {
+    let options = Options {
+        etag: true,
+        cache_control: Some("no-cache".parse().unwrap()),
+    };
+    let t = HelloTemplate { name: "world" };
+
+    let res = response_to(&t, &HeaderMap::new(), &options);
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["cache-control"], "no-cache");
+    let etag = res.headers()["etag"].clone();
+
+    let mut headers = HeaderMap::new();
+    headers.insert("if-none-match", etag.clone());
+    let res = response_to(&t, &headers, &options);
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(res.headers()["etag"], etag);
+    assert!(res.body().is_empty());
+
+    let res = response_to(&HelloTemplate { name: "you" }, &headers, &options);
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_ne!(res.headers()["etag"], etag);
+}
+
+// hand written, the derive would reject the mime type
+struct BadMime;
+
+impl fmt::Display for BadMime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("bad")
+    }
+}
+
+impl Template for BadMime {
+    fn mime() -> &'static str {
+        "text/html\r\n"
+    }
+
+    fn size_hint() -> usize {
+        3
+    }
+}
+
+#[test]
+fn test_bad_mime() {
+    let res = response(&BadMime);
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(res.body().is_empty());
+}
@@ -0,0 +1,79 @@
+use wearte::Template;
+
+#[derive(Template)]
+#[template(
+    src = "<p>{{ t \"hello\" name=user }}</p><p>{{ t \"cart.items\" count=items.len() }}</p>\
+           {{ t \"footer\" }}",
+    ext = "html",
+    locale_field = "lang"
+)]
+struct Cart<'a> {
+    lang: &'a str,
+    user: &'a str,
+    items: Vec<u32>,
+}
+
+#[test]
+fn test_i18n() {
+    let mut t = Cart {
+        lang: "en",
+        user: "<Ana>",
+        items: vec![1],
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<p>Hello, &lt;Ana&gt;!</p><p>One item</p>Made with {care}"
+    );
+
+    t.lang = "de_AT.UTF-8";
+    t.items = vec![1, 2, 3];
+    assert_eq!(
+        t.call().unwrap(),
+        "<p>Hallo, &lt;Ana&gt;!</p><p>3 Artikel</p>Made with {care}"
+    );
+
+    // fallback to `en`
+    t.lang = "fr";
+    assert_eq!(
+        t.call().unwrap(),
+        "<p>Hello, &lt;Ana&gt;!</p><p>3 items</p>Made with {care}"
+    );
+
+    let mut buf = vec![];
+    t.render_into(&mut buf).unwrap();
+    assert_eq!(buf, t.call().unwrap().as_bytes());
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each counts}}{{ t \"cart.items\" count=key }}\n{{/each}}",
+    ext = "txt",
+    locale_field = "lang"
+)]
+struct Counts {
+    lang: String,
+    counts: Vec<i64>,
+}
+
+#[test]
+fn test_i18n_plural() {
+    let t = Counts {
+        lang: "pl".into(),
+        counts: vec![1, 2, 5, 22, 112],
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "1 produkt\n2 produkty\n5 produktów\n22 produkty\n112 produktów\n"
+    );
+}
+
+#[derive(Template)]
+#[template(src = "{{ t \"hello\" name=name }}", ext = "txt")]
+struct Default<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_i18n_default() {
+    assert_eq!(Default { name: "Ana" }.call().unwrap(), "Hello, Ana!");
+}
@@ -0,0 +1,41 @@
+use wearte::Template;
+
+#[derive(Template)]
+#[template(path = "{locale}/terms.html", locale_field = "lang")]
+struct Terms<'a> {
+    lang: &'a str,
+    name: &'a str,
+    items: Vec<&'a str>,
+}
+
+#[test]
+fn test_locales() {
+    let mut t = Terms {
+        lang: "en-US",
+        name: "<Ana>",
+        items: vec!["a", "b"],
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<h1>Terms</h1>\n<p>Hello &lt;Ana&gt;, read them.</p>"
+    );
+
+    t.lang = "de";
+    assert_eq!(
+        t.call().unwrap(),
+        "<h1>AGB</h1>\n<ol><li>a</li><li>b</li></ol>\n<p>Hallo, &lt;Ana&gt;!</p>"
+    );
+    let mut buf = vec![];
+    t.render_into(&mut buf).unwrap();
+    assert_eq!(buf, t.call().unwrap().as_bytes());
+
+    // fallback
+    t.lang = "fr";
+    assert!(t.call().unwrap().starts_with("<h1>Terms</h1>"));
+}
+
+#[test]
+fn test_locales_hints() {
+    assert_eq!(Terms::mime(), "text/html");
+    assert!(Terms::size_hint() >= "<h1>AGB</h1>\n<ol></ol>\n<p></p>".len());
+}
@@ -0,0 +1,48 @@
+use wearte::Template;
+
+#[derive(Template)]
+#[template(path = "macros.html", whitespace = "standalone")]
+struct Form<'a> {
+    title: &'a str,
+    fields: Vec<(&'a str, &'a str)>,
+}
+
+#[test]
+fn test_macros() {
+    let t = Form {
+        title: "<Send>",
+        fields: vec![("name", "Ana"), ("email", "a@b")],
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<form>\n\
+         \x20 <label>name</label><input name=\"name\" value=\"Ana\">\n\
+         \x20 <label>email</label><input name=\"email\" value=\"a@b\">\n\
+         \x20 <button class=\"primary\">&lt;Send&gt;</button>\n\
+         </form>"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#macro item(n: usize, name)}}{{ n }}. {{ name }}{{#if n == total }}.{{else}}, {{/if}}{{/macro~}}
+           {{#each names}}{{> item(index, key) }}{{/each}}",
+    ext = "txt"
+)]
+struct List<'a> {
+    total: usize,
+    names: &'a [&'a str],
+}
+
+#[test]
+fn test_macro_scope() {
+    let t = List {
+        total: 2,
+        names: &["a", "b"],
+    };
+    assert_eq!(t.call().unwrap(), "1. a, 2. b.");
+
+    let mut buf = vec![];
+    t.render_into(&mut buf).unwrap();
+    assert_eq!(buf, b"1. a, 2. b.");
+}
@@ -0,0 +1,32 @@
+use wearte::Template;
+
+#[derive(Template)]
+#[template(src = "<feed></feed>", ext = "xml", mime = "application/atom+xml")]
+struct Feed;
+
+#[derive(Template)]
+#[template(src = "{{ name }}", ext = "txt", charset = "utf-8")]
+struct Text<'a> {
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "fragment.tmpl")]
+struct Fragment<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_mime() {
+    assert_eq!(Feed::mime(), "application/atom+xml");
+    assert_eq!(Text::mime(), "text/plain; charset=utf-8");
+    assert_eq!(Text { name: "a" }.dyn_mime(), "text/plain; charset=utf-8");
+}
+
+#[test]
+fn test_config() {
+    assert_eq!(Fragment::mime(), "text/html");
+    assert_eq!(Fragment { name: "a" }.call().unwrap(), "<p>a</p>");
+    // escaped as HTML
+    assert_eq!(Fragment { name: "<a>" }.call().unwrap(), "<p>&lt;a&gt;</p>");
+}
@@ -0,0 +1,68 @@
+use wearte::Template;
+
+#[derive(Template)]
+#[template(
+    src = "<ul class=\"list\">
+    <!-- items -->
+    {{#each items}}
+    <li class=\"{{ key }}\">  {{ key }}  </li>
+    {{/each}}
+</ul>
+<pre>
+  {{ pre }}
+</pre>",
+    ext = "html",
+    minify = true
+)]
+struct MinifyTemplate<'a> {
+    items: &'a [&'a str],
+    pre: &'a str,
+}
+
+#[test]
+fn test_minify() {
+    let t = MinifyTemplate {
+        items: &["a", "b c"],
+        pre: "<x>",
+    };
+    assert_eq!(
+        "<ul class=list>  <li class=\"a\"> a </li>  <li class=\"b c\"> b c </li>  </ul><pre>\n  &lt;x&gt;\n</pre>",
+        t.call().unwrap()
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "<p>\n  {{ name }}\n  <b>{{ role }}</b>\n</p>",
+    ext = "html",
+    minify = true
+)]
+struct TextTemplate<'a> {
+    name: &'a str,
+    role: &'a str,
+}
+
+#[test]
+fn test_minify_text() {
+    let t = TextTemplate {
+        name: "Ana",
+        role: "admin",
+    };
+    assert_eq!("<p> Ana <b>admin</b></p>", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(
+    src = "<div>\n  <b>Hello</b>\n  <i>{{ name }}</i>\n</div>",
+    ext = "html",
+    minify = true
+)]
+struct InlineTemplate<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_minify_inline() {
+    let t = InlineTemplate { name: "world" };
+    assert_eq!("<div><b>Hello</b> <i>world</i></div>", t.call().unwrap());
+}
@@ -0,0 +1,82 @@
+use wearte::Template;
+
+#[derive(Template)]
+#[template(src = "<div class=\"card\">{{ title }}</div>", ext = "html")]
+struct Card<'a> {
+    title: &'a str,
+}
+
+#[derive(Template)]
+#[template(
+    src = "<main>{{ card }}{{#each cards}}{{ key }}{{/each}}{{ self.footer }}</main>",
+    ext = "html"
+)]
+struct Page<'a> {
+    card: Card<'a>,
+    cards: Vec<Card<'a>>,
+    footer: &'a Card<'a>,
+}
+
+#[test]
+fn test_nested() {
+    let footer = Card { title: "a & b" };
+    let t = Page {
+        card: Card { title: "<Hi>" },
+        cards: vec![Card { title: "1" }, Card { title: "2" }],
+        footer: &footer,
+    };
+    let expect = "<main><div class=\"card\">&lt;Hi&gt;</div>\
+                  <div class=\"card\">1</div><div class=\"card\">2</div>\
+                  <div class=\"card\">a &amp; b</div></main>";
+    assert_eq!(t.call().unwrap(), expect);
+
+    let mut buf = vec![];
+    t.render_into(&mut buf).unwrap();
+    assert_eq!(buf, expect.as_bytes());
+}
+
+#[test]
+fn test_nested_size_hint() {
+    // `card` and `footer` are fields, the loop items aren't
+    assert!(Page::size_hint() >= 2 * Card::size_hint() + "<main></main>".len());
+}
+
+#[derive(Template)]
+#[template(src = "{{ text }}", ext = "txt")]
+struct Text<'a> {
+    text: &'a str,
+}
+
+#[derive(Template)]
+#[template(src = "<svg>{{ title }}</svg>", ext = "svg")]
+struct Icon<'a> {
+    title: &'a str,
+}
+
+#[derive(Template)]
+#[template(src = "<p>{{ icon }}</p>", ext = "html")]
+struct Figure<'a> {
+    icon: Icon<'a>,
+}
+
+// a field of a template with other MIME type, e.g. `Text`, is a compile error,
+// the loop items are checked at run time
+#[derive(Template)]
+#[template(src = "<p>{{#each texts}}{{ key }}{{/each}}</p>", ext = "html")]
+struct Paragraph<'a> {
+    texts: Vec<Text<'a>>,
+}
+
+#[test]
+fn test_nested_mime() {
+    let t = Figure {
+        icon: Icon { title: "<b>" },
+    };
+    assert!(t.call().unwrap().starts_with("<p><svg>"));
+
+    let t = Paragraph {
+        texts: vec![Text { text: "<b>" }],
+    };
+    assert!(t.call().is_err());
+    assert!(t.render_into(&mut vec![]).is_err());
+}
@@ -0,0 +1,46 @@
+use wearte::Template;
+
+#[derive(Template)]
+#[template(path = "overlay/page.html")]
+struct Page<'a> {
+    name: &'a str,
+}
+
+// only in the second template directory
+#[derive(Template)]
+#[template(path = "footer.html")]
+struct Footer<'a> {
+    name: &'a str,
+}
+
+// overridden embedded file
+#[derive(Template)]
+#[template(src = "<a>{{> raw \"overlay/mark.svg\" }}</a>", ext = "html")]
+struct Mark;
+
+#[test]
+#[cfg(not(feature = "white-label"))]
+fn test_dirs() {
+    let t = Page { name: "Shop" };
+    assert_eq!(t.call().unwrap(), "<header>Acme</header>\n<p>Shop</p>");
+
+    let t = Footer { name: "Shop" };
+    assert_eq!(t.call().unwrap(), "<footer>Acme</footer>");
+
+    assert_eq!(Mark.call().unwrap(), "<a><i>base</i></a>");
+}
+
+#[test]
+#[cfg(feature = "white-label")]
+fn test_overlays() {
+    let t = Page { name: "Shop" };
+    assert_eq!(
+        t.call().unwrap(),
+        "<header><b>Shop</b></header>\n<p>Shop</p>"
+    );
+
+    let t = Footer { name: "Shop" };
+    assert_eq!(t.call().unwrap(), "<footer><b>Shop</b></footer>");
+
+    assert_eq!(Mark.call().unwrap(), "<a><i>white</i></a>");
+}
@@ -0,0 +1,38 @@
+use wearte::{pool, Template};
+
+#[derive(Template)]
+#[template(src = "Hello, {{ name }}!", ext = "html")]
+struct Hello<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_call_into_string() {
+    let mut buf = String::from("> ");
+    Hello { name: "<world>" }.call_into_string(&mut buf).unwrap();
+    Hello { name: "again" }.call_into_string(&mut buf).unwrap();
+    assert_eq!(buf, "> Hello, &lt;world&gt;!Hello, again!");
+}
+
+#[test]
+fn test_pool() {
+    let out = pool::call(&Hello { name: "world" }).unwrap();
+    assert_eq!(&*out, "Hello, world!");
+    let ptr = out.as_ptr();
+    drop(out);
+
+    // the buffer of the last render is reused
+    let out = pool::call(&Hello { name: "pool" }).unwrap();
+    assert_eq!(out.as_str(), "Hello, pool!");
+    assert_eq!(out.as_ptr(), ptr);
+    assert_eq!(out.into_string(), "Hello, pool!");
+}
+
+#[cfg(feature = "with-bytes")]
+#[test]
+fn test_bytes() {
+    let a = pool::call_bytes(&Hello { name: "a" }).unwrap();
+    let b = pool::call_bytes(&Hello { name: "b" }).unwrap();
+    assert_eq!(&a[..], b"Hello, a!");
+    assert_eq!(&b[..], b"Hello, b!");
+}
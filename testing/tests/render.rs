@@ -0,0 +1,78 @@
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+use std::sync::Arc;
+
+use wearte::escapers::Escaper;
+use wearte::{Render, Template};
+
+#[derive(Template)]
+#[template(
+    src = "{{ price }} {{ c }} {{ cow }} {{ arc }} {{ opt }}|{{ none }}",
+    ext = "html"
+)]
+struct StdTemplate<'a> {
+    price: f64,
+    c: char,
+    cow: Cow<'a, str>,
+    arc: Arc<str>,
+    opt: Option<&'a str>,
+    none: Option<u8>,
+}
+
+#[test]
+fn test_std() {
+    let t = StdTemplate {
+        price: 1.5,
+        c: '<',
+        cow: Cow::Borrowed("&"),
+        arc: Arc::from(">"),
+        opt: Some("\""),
+        none: None,
+    };
+    assert_eq!("1.5 &lt; &amp; &gt; &quot;|", t.call().unwrap());
+}
+
+struct User {
+    name: &'static str,
+}
+
+impl Render for User {
+    fn render<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str("@")?;
+        E::escape(self.name, writer)
+    }
+}
+
+#[derive(Template)]
+#[template(src = "{{ user }}", ext = "html")]
+struct UserTemplate {
+    user: User,
+}
+
+#[test]
+fn test_user() {
+    let t = UserTemplate {
+        user: User { name: "<b>" },
+    };
+    assert_eq!("@&lt;b&gt;", t.call().unwrap());
+}
+
+struct Tag(&'static str);
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{}>", self.0)
+    }
+}
+
+#[derive(Template)]
+#[template(src = "{{ tag }}{{ 1 + 1 }}", ext = "html")]
+struct DisplayTemplate {
+    tag: Tag,
+}
+
+#[test]
+fn test_display() {
+    let t = DisplayTemplate { tag: Tag("b") };
+    assert_eq!("&lt;b&gt;2", t.call().unwrap());
+}
@@ -0,0 +1,49 @@
+use wearte::Template;
+
+#[derive(Template)]
+#[template(src = "Hello, {{ name }}!", ext = "txt")]
+struct Hello<'a> {
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[template(src = "<ul>{{#each items}}<li>{{ key }}</li>{{/each}}</ul>", ext = "html")]
+struct List<'a> {
+    items: &'a [&'a str],
+}
+
+#[derive(Template)]
+#[template(src = "{{#if a }}short{{else}}longer{{/if}}", ext = "txt")]
+struct Branch {
+    a: bool,
+}
+
+#[test]
+fn test_size_hint() {
+    assert_eq!(Hello::size_hint(), 7 + 16 + 1);
+    assert_eq!(List::size_hint(), 4 + (4 + 16 + 5) * 8 + 5);
+    assert_eq!(Branch::size_hint(), 6);
+}
+
+#[derive(Template)]
+#[template(src = "{{#each items}}{{ key }}{{/each}}", ext = "txt", size_hint = "adaptive")]
+struct Adaptive<'a> {
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_adaptive() {
+    assert_eq!(Adaptive::size_hint(), 16 * 8);
+
+    let items = ["a"; 200];
+    let t = Adaptive { items: &items };
+    assert_eq!(t.call().unwrap().len(), 200);
+    assert_eq!(Adaptive::size_hint(), 200);
+
+    let mut buf = b"ab".to_vec();
+    Adaptive { items: &items[..100] }
+        .render_into_vec(&mut buf)
+        .unwrap();
+    assert_eq!(buf.len(), 102);
+    assert_eq!(Adaptive::size_hint(), 175);
+}
@@ -0,0 +1,134 @@
+#![cfg(feature = "with-bytes")]
+use std::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
+
+use wearte::{stream, Template};
+
+#[derive(Template)]
+#[template(
+    src = "id,name\n{{#each rows}}{{ key.0 }},{{ key.1 }}\n{{/each}}",
+    ext = "txt"
+)]
+struct Csv {
+    rows: Vec<(usize, String)>,
+}
+
+fn csv(n: usize) -> Csv {
+    Csv {
+        rows: (0..n).map(|i| (i, format!("name {}", i))).collect(),
+    }
+}
+
+#[test]
+fn test_chunks() {
+    let expected = csv(1000).call().unwrap();
+
+    let mut chunks = stream::chunks(csv(1000), 64);
+    let mut out = vec![];
+    let mut count = 0;
+    for chunk in &mut chunks {
+        // chunks end with a whole row
+        assert!(chunk.ends_with(b"\n"));
+        assert!(chunk.len() < 64 + 16);
+        out.extend_from_slice(&chunk);
+        count += 1;
+    }
+    assert!(chunks.error().is_none());
+    assert!(100 < count);
+    assert_eq!(out, expected.as_bytes());
+}
+
+static RENDERED: AtomicUsize = AtomicUsize::new(0);
+
+struct Counted(usize);
+
+impl fmt::Display for Counted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        RENDERED.fetch_add(1, Ordering::SeqCst);
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Template)]
+#[template(src = "{{#each rows}}{{ key }}\n{{/each}}", ext = "txt")]
+struct Counter {
+    rows: Vec<Counted>,
+}
+
+#[test]
+fn test_drop() {
+    let mut chunks = stream::chunks(
+        Counter {
+            rows: (0..10_000).map(Counted).collect(),
+        },
+        16,
+    );
+    assert_eq!(&chunks.next().unwrap()[..], b"0\n1\n2\n3\n4\n5\n6\n7\n");
+    drop(chunks);
+
+    // the rendering stops a few rows after the consumer is gone
+    thread::sleep(Duration::from_millis(200));
+    let rendered = RENDERED.load(Ordering::SeqCst);
+    assert!(rendered < 100, "{} rows rendered", rendered);
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(RENDERED.load(Ordering::SeqCst), rendered);
+}
+
+#[cfg(feature = "with-futures")]
+#[test]
+fn test_stream() {
+    use futures::{executor::block_on, StreamExt};
+
+    // `Chunks` is also an `Iterator`
+    let chunks: Vec<_> = block_on(StreamExt::collect(stream::chunks(csv(100), 32)));
+    let out = chunks
+        .into_iter()
+        .map(Result::unwrap)
+        .fold(vec![], |mut out, c| {
+            out.extend_from_slice(&c);
+            out
+        });
+    assert_eq!(out, csv(100).call().unwrap().as_bytes());
+}
+
+struct Panics;
+
+impl std::fmt::Display for Panics {
+    fn fmt(&self, _: &mut std::fmt::Formatter) -> std::fmt::Result {
+        panic!("render")
+    }
+}
+
+#[derive(Template)]
+#[template(src = "{{#each rows}}{{ key }}\n{{/each}}{{ last }}", ext = "txt")]
+struct Broken {
+    rows: Vec<usize>,
+    last: Panics,
+}
+
+#[test]
+fn test_panic() {
+    let t = Broken {
+        rows: (0..100).collect(),
+        last: Panics,
+    };
+    let mut chunks = stream::chunks(t, 16);
+    assert!(chunks.by_ref().count() > 0);
+    assert_eq!(chunks.error(), Some(std::fmt::Error));
+
+    #[cfg(feature = "with-futures")]
+    {
+        use futures::{executor::block_on, StreamExt};
+
+        let t = Broken {
+            rows: vec![],
+            last: Panics,
+        };
+        let chunks: Vec<_> = block_on(StreamExt::collect(stream::chunks(t, 16)));
+        assert!(chunks.last().unwrap().is_err());
+    }
+}
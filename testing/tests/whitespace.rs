@@ -0,0 +1,89 @@
+use wearte::Template;
+
+#[derive(Template)]
+#[template(
+    src = "<ul>
+  {{#each items}}
+  <li>{{ key }}</li>
+  {{/each}}
+</ul>",
+    ext = "html",
+    whitespace = "suppress"
+)]
+struct SuppressTemplate<'a> {
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_suppress() {
+    let t = SuppressTemplate { items: &["a", "b"] };
+    assert_eq!("<ul><li>a</li><li>b</li></ul>", t.call().unwrap());
+}
+
+// `+` keeps the whitespace around a single tag
+#[derive(Template)]
+#[template(
+    src = "<p>
+  {{#each items}}
+  <b>{{ key }}</b>
+  {{+/each}}
+</p>",
+    ext = "html",
+    whitespace = "suppress"
+)]
+struct KeepTemplate<'a> {
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_suppress_keep() {
+    let t = KeepTemplate { items: &["a", "b"] };
+    assert_eq!("<p><b>a</b>\n  <b>b</b>\n  </p>", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(
+    src = "Items:
+{{! list !}}
+{{#each items}}
+  - {{ key }}
+{{/each}}
+{{#if items.is_empty()}}
+  none
+{{else}}
+  {{~ items.len() }} total
+{{/if}}
+",
+    ext = "txt",
+    whitespace = "standalone"
+)]
+struct StandaloneTemplate<'a> {
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_standalone() {
+    let t = StandaloneTemplate { items: &["a", "b"] };
+    assert_eq!("Items:\n  - a\n  - b\n2 total\n", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(src = "{{#if true}}\n  a\n{{/if}}", ext = "txt")]
+struct PreserveTemplate;
+
+#[test]
+fn test_preserve() {
+    assert_eq!("\n  a\n", PreserveTemplate.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(path = "hello.txt", trim_trailing = false)]
+struct TrailingTemplate<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_trim_trailing() {
+    let hello = TrailingTemplate { name: "world" };
+    assert_eq!("Hello, world!\n", hello.call().unwrap());
+}
@@ -0,0 +1,54 @@
+// Responder of the templates for actix-web 4
+pub use ::actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
+
+use ::actix_web::http::header::{Accept, Header, Quality};
+use ::actix_web::mime::{Mime, STAR};
+
+use crate::Template;
+
+/// Renders `t` as the response to `req`, with `406 Not Acceptable` when the
+/// `Accept` header of the request excludes the mime type of the template
+pub fn respond<T: Template>(t: &T, req: &HttpRequest) -> HttpResponse {
+    if !accepts(req, T::mime()) {
+        return HttpResponse::NotAcceptable().finish();
+    }
+
+    let mut body = Vec::with_capacity(T::size_hint());
+    match t.render_into_vec(&mut body) {
+        Ok(()) => HttpResponse::Ok().content_type(T::mime()).body(body),
+        Err(_) => HttpResponse::InternalServerError().body("Template parsing error"),
+    }
+}
+
+// The most specific media range matching the mime decides, a missing or invalid
+// header accepts anything
+fn accepts(req: &HttpRequest, mime: &str) -> bool {
+    let accept = match Accept::parse(req) {
+        Ok(Accept(items)) if !items.is_empty() => items,
+        _ => return true,
+    };
+    let mime = match mime.parse::<Mime>() {
+        Ok(mime) => mime,
+        Err(_) => return true,
+    };
+
+    accept
+        .iter()
+        .filter_map(|q| {
+            let range = &q.item;
+            let specificity = if range.type_() == STAR {
+                0
+            } else if range.type_() != mime.type_() {
+                return None;
+            } else if range.subtype() == STAR {
+                1
+            } else if range.subtype() != mime.subtype() {
+                return None;
+            } else {
+                2
+            };
+            Some((specificity, q.quality))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .is_some_and(|(_, quality)| quality > Quality::ZERO)
+}
@@ -0,0 +1,19 @@
+// `IntoResponse` of the templates for axum
+pub use axum_core::response::{IntoResponse, Response};
+
+use http::{header::CONTENT_TYPE, HeaderValue, StatusCode};
+
+use crate::Template;
+
+/// Renders `t` as a response with its mime type, or `500 Internal Server Error`
+pub fn into_response<T: Template>(t: &T) -> Response {
+    let mut body = Vec::with_capacity(T::size_hint());
+    // the derive checks the mime type, a hand written `Template` may not
+    match (
+        t.render_into_vec(&mut body),
+        HeaderValue::from_str(T::mime()),
+    ) {
+        (Ok(()), Ok(mime)) => ([(CONTENT_TYPE, mime)], body).into_response(),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "Template parsing error").into_response(),
+    }
+}
@@ -0,0 +1,122 @@
+// Conversion of the templates to `http::Response`, for any server built on the
+// `http` crate
+use std::fmt::Write;
+
+use ::http::header::{HeaderValue, CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+pub use ::http::{HeaderMap, Response, StatusCode};
+
+use bytes::{Bytes, BytesMut};
+
+use crate::Template;
+
+/// Options of [`response_to`](fn.response_to.html)
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Adds a strong `ETag` of the rendered bytes, and answers `304 Not Modified`
+    /// when it's in the `If-None-Match` of the request
+    pub etag: bool,
+    /// `Cache-Control` of the response
+    pub cache_control: Option<HeaderValue>,
+}
+
+/// Renders `t` as a `200 OK` response, or `500 Internal Server Error`
+pub fn response<T: Template>(t: &T) -> Response<Bytes> {
+    response_to(t, &HeaderMap::new(), &Options::default())
+}
+
+/// Renders `t` as the response to a request with the `headers`
+pub fn response_to<T: Template>(t: &T, headers: &HeaderMap, options: &Options) -> Response<Bytes> {
+    let mut body = BytesMut::new();
+    if t.render_into(&mut body).is_err() {
+        return with_status(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    let body = body.freeze();
+
+    let etag = if options.etag {
+        Some(etag(&body))
+    } else {
+        None
+    };
+    let mut res = match etag {
+        Some(ref etag) if matches(headers, etag) => with_status(StatusCode::NOT_MODIFIED),
+        _ => match content_type(T::mime()) {
+            Some(mime) => {
+                let mut res = Response::new(body);
+                res.headers_mut().insert(CONTENT_TYPE, mime);
+                res
+            }
+            None => return with_status(StatusCode::INTERNAL_SERVER_ERROR),
+        },
+    };
+
+    if let Some(etag) = etag {
+        res.headers_mut()
+            .insert(ETAG, HeaderValue::from_str(&etag).unwrap());
+    }
+    if let Some(ref cache_control) = options.cache_control {
+        res.headers_mut()
+            .insert(CACHE_CONTROL, cache_control.clone());
+    }
+
+    res
+}
+
+fn with_status(status: StatusCode) -> Response<Bytes> {
+    let mut res = Response::new(Bytes::new());
+    *res.status_mut() = status;
+    res
+}
+
+// the output of the templates is always utf-8, unless the `charset` attribute says otherwise.
+// The derive checks the mime type, a hand written `Template` may not
+fn content_type(mime: &str) -> Option<HeaderValue> {
+    if mime.contains("charset=") {
+        HeaderValue::from_str(mime).ok()
+    } else {
+        HeaderValue::from_str(&format!("{}; charset=utf-8", mime)).ok()
+    }
+}
+
+// FNV-1a 64 of the body, quoted
+fn etag(body: &[u8]) -> String {
+    let hash = body.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    });
+
+    let mut etag = String::with_capacity(18);
+    write!(etag, "\"{:016x}\"", hash).unwrap();
+    etag
+}
+
+// `If-None-Match` uses the weak comparison
+fn matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_etag() {
+        assert_eq!(etag(b""), "\"cbf29ce484222325\"");
+        assert_eq!(etag(b"a"), "\"af63dc4c8601ec8c\"");
+    }
+
+    #[test]
+    fn test_matches() {
+        let mut headers = HeaderMap::new();
+        assert!(!matches(&headers, "\"a\""));
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("\"b\", W/\"a\""));
+        assert!(matches(&headers, "\"a\""));
+        assert!(!matches(&headers, "\"c\""));
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(matches(&headers, "\"c\""));
+    }
+}
@@ -1,42 +1,161 @@
 // TODO: document
 
-use std::{fmt, io};
+use std::{
+    fmt::{self, Write as _},
+    io,
+};
 
 pub use wearte_derive::Template;
-pub use wearte_helpers::{helpers::MarkupAsStr, Error, Result};
+pub use wearte_helpers::{
+    helpers::{escapers, Buffer, BufferWriter, EscapeWriter, Html, PluralCount, Render, SizeHint},
+    html, Error, Result,
+};
 
+#[doc(hidden)]
+pub use self::nested::{
+    child_accepts, same_mime, Child, ChildMime, ChildOther, ChildTemplate, TemplateMime,
+    ViaTemplate,
+};
+#[doc(hidden)]
+pub use wearte_helpers::helpers::{negotiate, ViaDisplay, ViaRender, ViaStr, Wrap};
+
+#[cfg(feature = "with-actix-web")]
+pub mod actix_web;
+#[cfg(feature = "with-axum")]
+pub mod axum;
+#[cfg(feature = "with-http")]
+pub mod http;
+mod nested;
+pub mod pool;
 pub mod rerun;
+#[cfg(feature = "with-bytes")]
+pub mod stream;
 
 // TODO: document
-pub trait Template: fmt::Display {
-    // esto crea un string fmt sobre Template y te el String
+// Los metodos con `where Self: Sized` son la parte estatica, el resto se puede usar
+// desde `dyn Template`
+pub trait Template: fmt::Display + StaticTemplate {
+    // esto crea un string fmt sobre Template y te el String. Da `fmt::Error` si un
+    // template anidado tiene un tipo mime que el escaper no acepta, p. ej. texto dentro
+    // de html; en los campos de un template derivado es un error de compilacion
     fn call(&self) -> Result<String> {
-        let mut buf = String::with_capacity(Self::size_hint());
-        self.call_into_fmt(&mut buf).map(|_| buf)
+        let mut buf = String::new();
+        self.call_into_string(&mut buf).map(|_| buf)
+    }
+
+    // añade la salida al final de un string, para reusarlo
+    fn call_into_string(&self, buf: &mut String) -> fmt::Result {
+        let len = buf.len();
+        buf.reserve(self.dyn_size_hint());
+        self.call_into_fmt(buf)?;
+        self.record_size(buf.len() - len);
+        Ok(())
     }
 
     // esto es para un tipo string vect o algo asi
-    fn call_into_fmt(&self, writer: &mut fmt::Write) -> fmt::Result {
+    fn call_into_fmt(&self, writer: &mut dyn fmt::Write) -> fmt::Result {
         write!(writer, "{}", self)
     }
 
     // esto es para un archivo, stdout
-    fn call_into_io(&self, writer: &mut io::Write) -> io::Result<()> {
+    fn call_into_io(&self, writer: &mut dyn io::Write) -> io::Result<()> {
         write!(writer, "{}", self)
     }
 
+    // escribe directamente en bytes, sin pasar por fmt::Formatter
+    fn render_into<B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result
+    where
+        Self: Sized,
+    {
+        write!(BufferWriter::new(buf), "{}", self)
+    }
+
+    fn render_into_vec(&self, buf: &mut Vec<u8>) -> fmt::Result
+    where
+        Self: Sized,
+    {
+        let len = buf.len();
+        self.render_into(buf)?;
+        self.record_size(buf.len() - len);
+        Ok(())
+    }
+
+    // salida de un template anidado como markup seguro
+    fn as_html(&self) -> Result<Html> {
+        self.call().map(Html::trusted)
+    }
+
     // https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types
     fn mime() -> &'static str
     where
         Self: Sized;
 
     // heuristica de allocation
-    fn size_hint() -> usize;
+    fn size_hint() -> usize
+    where
+        Self: Sized;
+
+    // `mime` y `size_hint` para `dyn Template`
+    fn dyn_mime(&self) -> &'static str {
+        self.static_mime()
+    }
+
+    fn dyn_size_hint(&self) -> usize {
+        self.static_size_hint()
+    }
+
+    // guarda el tamaño de la salida, con `size_hint = "adaptive"`
+    #[doc(hidden)]
+    fn record_size(&self, _len: usize) {}
 }
 
-#[cfg(feature = "with-actix-web")]
-pub mod actix_web {
-    pub use actix_web::{
-        error::ErrorInternalServerError, Error, HttpRequest, HttpResponse, Responder,
-    };
+// `mime` y `size_hint` desde `&self`, para los metodos por defecto de `dyn Template`
+#[doc(hidden)]
+pub trait StaticTemplate {
+    fn static_mime(&self) -> &'static str;
+
+    fn static_size_hint(&self) -> usize;
+}
+
+impl<T: Template> StaticTemplate for T {
+    #[inline]
+    fn static_mime(&self) -> &'static str {
+        T::mime()
+    }
+
+    #[inline]
+    fn static_size_hint(&self) -> usize {
+        T::size_hint()
+    }
+}
+
+// referencias a un template, para los templates anidados
+impl<T: Template> Template for &T {
+    fn call_into_fmt(&self, writer: &mut dyn fmt::Write) -> fmt::Result {
+        (**self).call_into_fmt(writer)
+    }
+
+    fn render_into<B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        (**self).render_into(buf)
+    }
+
+    fn mime() -> &'static str {
+        T::mime()
+    }
+
+    fn size_hint() -> usize {
+        T::size_hint()
+    }
+
+    fn dyn_mime(&self) -> &'static str {
+        (**self).dyn_mime()
+    }
+
+    fn dyn_size_hint(&self) -> usize {
+        (**self).dyn_size_hint()
+    }
+
+    fn record_size(&self, len: usize) {
+        (**self).record_size(len)
+    }
 }
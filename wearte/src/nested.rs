@@ -0,0 +1,143 @@
+use std::{
+    fmt::{self, Write},
+    marker::PhantomData,
+};
+
+use wearte_helpers::helpers::{escapers::Escaper, Buffer, Wrap};
+
+use crate::Template;
+
+// Nested templates, an expression whose type implements `Template` is written
+// without escaping, it is already escaped by its own template. Resolved before
+// `ViaDisplay` like `ViaRender`, the output is an error when the escaper of the
+// parent doesn't accept the mime type of the child, e.g. text inside html. The
+// fields of a derived template are checked at compile time, see `ChildMime`.
+#[doc(hidden)]
+pub trait ViaTemplate {
+    fn render_escaped<E: Escaper, W: Write>(&self, writer: &mut W) -> fmt::Result;
+    fn render_escaped_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result;
+    fn render_raw_bytes<B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result;
+}
+
+impl<'a, T: Template> ViaTemplate for Wrap<'a, T> {
+    #[inline]
+    fn render_escaped<E: Escaper, W: Write>(&self, writer: &mut W) -> fmt::Result {
+        if !E::accepts(T::mime()) {
+            return Err(fmt::Error);
+        }
+        self.0.call_into_fmt(writer)
+    }
+
+    #[inline]
+    fn render_escaped_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        if !E::accepts(T::mime()) {
+            return Err(fmt::Error);
+        }
+        self.0.render_into(buf)
+    }
+
+    #[inline]
+    fn render_raw_bytes<B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        self.0.render_into(buf)
+    }
+}
+
+// Size hint of a field in `Template::size_hint`, generated code calls
+// `(&Child::<T>::new()).child_size_hint()`, the child's hint when `T`
+// implements `Template` and zero otherwise.
+#[doc(hidden)]
+pub struct Child<T: ?Sized>(PhantomData<T>);
+
+impl<T: ?Sized> Child<T> {
+    #[inline]
+    pub const fn new() -> Self {
+        Child(PhantomData)
+    }
+}
+
+impl<T: ?Sized> Default for Child<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[doc(hidden)]
+pub trait ChildTemplate {
+    fn child_size_hint(&self) -> usize;
+}
+
+impl<T: Template> ChildTemplate for Child<T> {
+    #[inline]
+    fn child_size_hint(&self) -> usize {
+        T::size_hint()
+    }
+}
+
+#[doc(hidden)]
+pub trait ChildOther {
+    fn child_size_hint(&self) -> usize;
+}
+
+impl<T: ?Sized> ChildOther for &Child<T> {
+    #[inline]
+    fn child_size_hint(&self) -> usize {
+        0
+    }
+}
+
+// MIME type known at compile time, implemented by the derive. `mime` isn't a
+// `const fn` and an associated const would make `dyn Template` impossible
+#[doc(hidden)]
+pub trait TemplateMime {
+    const MIME: &'static str;
+}
+
+impl<T: TemplateMime + ?Sized> TemplateMime for &T {
+    const MIME: &'static str = T::MIME;
+}
+
+// MIME type of a field in the generated code, `Child::<T>::MIME` is the
+// inherent constant when `T` implements `TemplateMime` and `None` otherwise,
+// inherent items take precedence over the ones of traits. Checked with
+// `const { assert!(escapers::accepts_mime(<E as Escaper>::MIMES, ..)) }`
+#[doc(hidden)]
+pub trait ChildMime {
+    const MIME: Option<&'static str> = None;
+}
+
+impl<T: ?Sized> ChildMime for Child<T> {}
+
+impl<T: TemplateMime + ?Sized> Child<T> {
+    pub const MIME: Option<&'static str> = Some(T::MIME);
+}
+
+#[doc(hidden)]
+pub const fn child_accepts(mimes: &[&str], mime: Option<&str>) -> bool {
+    match mime {
+        Some(mime) => wearte_helpers::helpers::escapers::accepts_mime(mimes, mime),
+        None => true,
+    }
+}
+
+// Variants of an enum delegating to other templates, unknown MIME types agree
+#[doc(hidden)]
+pub const fn same_mime(a: Option<&str>, b: Option<&str>) -> bool {
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) => (a.as_bytes(), b.as_bytes()),
+        _ => return true,
+    };
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
@@ -0,0 +1,115 @@
+// Thread local buffers reused between renders, so rendering lots of small
+// templates doesn't allocate once the buffers are warm
+use std::{cell::RefCell, fmt, mem, ops::Deref};
+
+#[cfg(feature = "with-bytes")]
+use bytes::{Bytes, BytesMut};
+
+use crate::{Result, Template};
+
+// buffers kept by thread, bigger ones are freed
+const MAX_BUFFERS: usize = 16;
+const MAX_CAPACITY: usize = 1 << 20;
+
+thread_local! {
+    static STRINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+#[cfg(feature = "with-bytes")]
+thread_local! {
+    static BYTES: RefCell<BytesMut> = RefCell::new(BytesMut::new());
+}
+
+/// Rendered output in a pooled buffer, the buffer goes back to the pool on drop
+pub struct Pooled(String);
+
+impl Pooled {
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Takes the buffer out of the pool
+    #[inline]
+    pub fn into_string(mut self) -> String {
+        mem::take(&mut self.0)
+    }
+}
+
+impl Deref for Pooled {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Pooled {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Pooled {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for Pooled {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl Drop for Pooled {
+    fn drop(&mut self) {
+        let capacity = self.0.capacity();
+        if capacity == 0 || MAX_CAPACITY < capacity {
+            return;
+        }
+
+        let mut buf = mem::take(&mut self.0);
+        buf.clear();
+        // the pool is already gone when the thread is being destroyed
+        let _ = STRINGS.try_with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.len() < MAX_BUFFERS {
+                pool.push(buf);
+            }
+        });
+    }
+}
+
+/// Renders `t` into a buffer of the thread pool
+pub fn call<T: Template>(t: &T) -> Result<Pooled> {
+    let buf = STRINGS
+        .try_with(|pool| pool.borrow_mut().pop())
+        .ok()
+        .and_then(|buf| buf)
+        .unwrap_or_default();
+
+    let mut out = Pooled(buf);
+    t.call_into_string(&mut out.0)?;
+    Ok(out)
+}
+
+/// Renders `t` into the thread buffer and splits the output off as `Bytes`
+///
+/// The allocation is reclaimed by the next renders once all the `Bytes` of it
+/// are dropped.
+#[cfg(feature = "with-bytes")]
+pub fn call_bytes<T: Template>(t: &T) -> Result<Bytes> {
+    // taken out of the cell so nested renders don't conflict
+    let mut buf = BYTES.with(|b| mem::take(&mut *b.borrow_mut()));
+    let res = t.render_into(&mut buf);
+    t.record_size(buf.len());
+    let out = buf.split().freeze();
+    BYTES.with(|b| *b.borrow_mut() = buf);
+
+    res.map(|_| out)
+}
@@ -14,11 +14,23 @@ pub fn when_changed() {
     let config = Config::new(&file);
 
     // rerun when dir change for add files
-    println!(
-        "cargo:rerun-if-changed={}",
-        config.get_dir().to_str().unwrap()
-    );
-    let mut stack = vec![config.get_dir().clone()];
+    let mut stack = vec![];
+    for dir in config.get_all_dirs() {
+        if dir.exists() {
+            println!("cargo:rerun-if-changed={}", dir.to_str().unwrap());
+            stack.push(dir);
+        }
+    }
+
+    // and the message catalogs and the assets
+    let i18n = config.i18n.as_ref().map(|i18n| &i18n.dir);
+    let assets = config.assets.as_ref().map(|assets| &assets.dir);
+    for dir in i18n.into_iter().chain(assets) {
+        if dir.exists() {
+            println!("cargo:rerun-if-changed={}", dir.to_str().unwrap());
+            stack.push(dir.clone());
+        }
+    }
     loop {
         if let Some(dir) = stack.pop() {
             for entry in fs::read_dir(dir).expect("valid directory") {
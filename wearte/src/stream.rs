@@ -0,0 +1,202 @@
+// Streaming rendering in bounded chunks
+//
+// The template is rendered in its own thread into a buffer that sends out a chunk
+// at the end of a loop iteration once it holds `size` bytes. The channel only holds
+// a couple of chunks, so the rendering waits for the consumer and the memory
+// stays bounded. Once the consumer drops the chunks, the rendering stops at the
+// end of the next loop iteration. A panic of the rendering is caught and sent as
+// an error like a `fmt::Error`, the output is never silently truncated.
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::mpsc::{sync_channel, Receiver, SyncSender},
+    thread,
+};
+
+#[cfg(feature = "with-futures")]
+use std::{
+    pin::Pin,
+    sync::{mpsc::TryRecvError, Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use bytes::{Bytes, BytesMut};
+
+use crate::{Buffer, Error, Result, Template};
+
+// chunks waiting in the channel
+const BOUND: usize = 2;
+// a chunk is sent out without waiting for a loop once it reaches `size * MAX_FACTOR`
+const MAX_FACTOR: usize = 4;
+
+#[cfg(feature = "with-futures")]
+type Wake = Arc<Mutex<Option<Waker>>>;
+
+/// Rendered chunks of a template
+///
+/// As an `Iterator` it blocks until the next chunk is ready, and with the feature
+/// `with-futures` it's also a `futures::Stream` for response bodies.
+pub struct Chunks {
+    rx: Receiver<Result<Bytes>>,
+    error: Option<Error>,
+    #[cfg(feature = "with-futures")]
+    waker: Wake,
+}
+
+impl Chunks {
+    /// Rendering error or panic, the chunks end at the first one
+    #[inline]
+    pub fn error(&self) -> Option<Error> {
+        self.error
+    }
+}
+
+/// Renders `t` in a new thread, in chunks of about `size` bytes
+pub fn chunks<T>(t: T, size: usize) -> Chunks
+where
+    T: Template + Send + 'static,
+{
+    let (tx, rx) = sync_channel(BOUND);
+    #[cfg(feature = "with-futures")]
+    let waker = Wake::default();
+
+    let mut sender = Sender {
+        buf: BytesMut::with_capacity(size),
+        size,
+        tx,
+        closed: false,
+        #[cfg(feature = "with-futures")]
+        waker: waker.clone(),
+    };
+    thread::spawn(move || {
+        let res = panic::catch_unwind(AssertUnwindSafe(|| t.render_into(&mut sender)))
+            .unwrap_or(Err(Error));
+        sender.flush();
+        if let Err(e) = res {
+            sender.send(Err(e));
+        }
+        sender.close();
+    });
+
+    Chunks {
+        rx,
+        error: None,
+        #[cfg(feature = "with-futures")]
+        waker,
+    }
+}
+
+impl Iterator for Chunks {
+    type Item = Bytes;
+
+    fn next(&mut self) -> Option<Bytes> {
+        match self.rx.recv() {
+            Ok(Ok(chunk)) => Some(chunk),
+            Ok(Err(e)) => {
+                self.error = Some(e);
+                None
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "with-futures")]
+impl futures_core::Stream for Chunks {
+    type Item = Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        for _ in 0..2 {
+            match this.rx.try_recv() {
+                Ok(chunk) => return Poll::Ready(Some(chunk)),
+                Err(TryRecvError::Disconnected) => return Poll::Ready(None),
+                // the waker is registered before trying again, a chunk sent in
+                // between would be lost otherwise
+                Err(TryRecvError::Empty) => {
+                    *this.waker.lock().unwrap() = Some(cx.waker().clone());
+                }
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+struct Sender {
+    buf: BytesMut,
+    size: usize,
+    tx: SyncSender<Result<Bytes>>,
+    // the consumer is gone, the rest of the output is dropped
+    closed: bool,
+    #[cfg(feature = "with-futures")]
+    waker: Wake,
+}
+
+impl Sender {
+    fn flush(&mut self) {
+        if !self.buf.is_empty() {
+            let chunk = self.buf.split().freeze();
+            self.send(Ok(chunk));
+            self.buf.reserve(self.size);
+        }
+    }
+
+    fn send(&mut self, chunk: Result<Bytes>) {
+        if self.closed {
+            return;
+        }
+
+        self.closed = self.tx.send(chunk).is_err();
+        #[cfg(feature = "with-futures")]
+        wake(&self.waker);
+    }
+
+    // the channel is disconnected before waking, so the stream sees the end
+    fn close(self) {
+        #[cfg(feature = "with-futures")]
+        let waker = self.waker.clone();
+        drop(self);
+        #[cfg(feature = "with-futures")]
+        wake(&waker);
+    }
+}
+
+#[cfg(feature = "with-futures")]
+fn wake(waker: &Wake) {
+    if let Some(waker) = waker.lock().unwrap().take() {
+        waker.wake();
+    }
+}
+
+impl Buffer for Sender {
+    #[inline]
+    fn extend_from_slice(&mut self, src: &[u8]) {
+        if self.closed {
+            return;
+        }
+
+        self.buf.extend_from_slice(src);
+        if self.size * MAX_FACTOR <= self.buf.len() {
+            self.flush();
+        }
+    }
+
+    // chunks are bounded, the size hint is for the whole output
+    #[inline]
+    fn reserve(&mut self, _additional: usize) {}
+
+    // the rendering stops at the next loop once the consumer is gone
+    #[inline]
+    fn chunk_boundary(&mut self) -> fmt::Result {
+        if self.size <= self.buf.len() {
+            self.flush();
+        }
+
+        if self.closed {
+            Err(fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
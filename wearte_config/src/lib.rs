@@ -7,15 +7,73 @@
 //!   - **`dir`**: name of template directory. If no value is given, a default directory
 //! **`templates`** will be used. If the defined directory is not found, an error
 //! will prompt.
+//!   - **`dirs`**: ordered list of template directories instead of `dir`, e.g.
+//!     `["themes/custom", "templates"]`. Templates and partials are searched in each
+//!     of them, the first one containing the file is used.
 //!   - **`debug`**: type of output of debug mode. The code and/or  ast generated by  wearte
 //! can be visualize, to do so, at most one of three possible values has to be given:
 //! `code`, `ast`, or `all`.
+//!   - **`whitespace`** (default: `preserve`): whitespace around block tags, helpers and
+//!     comments. With `preserve` it's only removed by the `~` markers, `suppress` behaves
+//!     as if every block tag had a `~` on both sides, and `standalone` removes the lines
+//!     containing only a block tag without `~` markers. The `+` marker keeps the
+//!     whitespace on its side of a block tag, e.g. `{{+/each}}`, overriding `suppress`
+//!     and `standalone`. It can be overridden by the template attribute `whitespace`.
+//!   - **`trim_trailing`** (default: `true`): Boolean, if set to `false` the trailing
+//!     whitespace of template and partial files is kept, e.g. the final line break.
+//!     It can be overridden by the template attribute `trim_trailing`.
+//!   - **`minify`** (default: `false`): Boolean, if set to `true` the literals of `html`
+//!     and `htm` templates are minified at compile time. Comments and line breaks next
+//!     to block-level tags are removed, other whitespace runs become a single space,
+//!     while `<pre>`, `<textarea>`, `<script>` and `<style>` are left untouched. It can
+//!     be overridden by the template attribute `minify`.
+//!   - **`warn_unused`** (default: `false`): Boolean, if set to `true` the derive emits a
+//!     compiler warning for each named field of the struct that the template never reads.
+//!     It can be overridden by the template attribute `warn_unused`.
+//!   - **`check_fields`** (default: `false`): Boolean, if set to `true` an identifier of
+//!     the template that isn't a named field of the struct is a compile error, with a
+//!     suggestion of the closest field. Leave it unset when fields come through `Deref`
+//!     or other macros. It can be overridden by the template attribute `check_fields`.
 //!
 //! - **`partials`** (partials aliasing - optional): each entry must be of the type
 //! `name_alias = "./alias/path/"`, where `./` makes reference to `dir` value. Path
 //! must exist, or error will be prompt. If the tag `partials` doesn't exist no aliasing
 //! will be possible.
 //!
+//! - **`escapers`** (escaping by extension - optional): each entry must be of the type
+//!   `extension = "escaper"`, where `escaper` is one of the built-in escapers, `html`,
+//!   `json`, `xml` or `none`, or the path to a type implementing `wearte::escapers::Escaper`.
+//!   Entries take precedence over the default escaping of html-like extensions.
+//!
+//! - **`mime`** (MIME type by extension - optional): each entry must be of the type
+//!   `extension = "type/subtype"`, used by `Template::mime` and the framework responders
+//!   instead of the type guessed from the extension. The template attributes `mime` and
+//!   `charset` take precedence. Templates served as `text/html` are HTML escaped unless
+//!   `escapers` or the `escape` attribute choose another escaper.
+//!
+//! - **`i18n`** (message catalogs - optional): with attributes
+//!   - **`dir`** (default: `locales`): directory of the gettext catalogs, a `.po` file
+//!     per locale named after it, e.g. `locales/de.po` or `locales/pt-BR.po`.
+//!   - **`default`**: locale of the source messages, its catalog must exist. Every
+//!     key translated by a template must be in it, and it's used when the locale of
+//!     the template has no catalog or the message isn't translated. It's also the
+//!     fallback of the templates with a `{locale}` path.
+//!
+//! - **`assets`** (static files - optional): with attributes
+//!   - **`dir`** (default: `static`): directory of the files of `{{ asset "path" }}` and
+//!     `{{ integrity "path" }}`, read at compile time.
+//!   - **`url`** (default: `/static/`): prefix of the fingerprinted URLs, e.g.
+//!     `/static/app.3f9a1c2b.css` for `app.css`.
+//!
+//! - **`overlays`** (template directories by cargo feature - optional): each entry must
+//!   be of the type `feature = "dir"`, the directory is searched before the ones of
+//!   `main` when the feature of the crate is enabled, overriding single templates and
+//!   partials. With several features enabled their directories are searched in
+//!   alphabetical order of the features. The features must be declared in the
+//!   `[features]` of the crate's `Cargo.toml`, the generated code checks them with
+//!   `cfg(feature = "...")`. A template can be overridden by 4 overlays at most, each
+//!   combination of them has its own implementation.
+//!
 //! - **`debug`** (debugging configuration - optional): in order to visualize clearly generated code
 //! in a debugging environment wearte gives it a tabulated format, and the possibility
 //! to see the number line use a color theme. Options are the following:
@@ -43,10 +101,30 @@
 //! [main]
 //! dir = "templates"
 //! debug = "all"
+//! whitespace = "standalone"
+//! minify = true
 //!
 //! [partials]
 //! alias = "./deep/more/deep"
 //!
+//! [escapers]
+//! json = "json"
+//! tex = "crate::escape::Latex"
+//!
+//! [mime]
+//! hbs = "text/html"
+//!
+//! [i18n]
+//! dir = "locales"
+//! default = "en"
+//!
+//! [assets]
+//! dir = "static"
+//! url = "/static/"
+//!
+//! [overlays]
+//! white-label = "themes/white"
+//!
 //! [debug]
 //! theme = "zenburn"
 //! number_line = true
@@ -64,8 +142,9 @@ use std::{
     path::{Path, PathBuf},
 };
 
+// Template directories, in search order
 #[derive(Debug)]
-pub struct Dir(PathBuf);
+pub struct Dir(Vec<PathBuf>);
 
 impl Dir {
     pub fn get_template(&self, path: PathBuf) -> PathBuf {
@@ -73,19 +152,36 @@ impl Dir {
             return path;
         }
 
-        let template = self.0.join(path);
-        if template.exists() {
-            template
-        } else {
-            panic!("template not found in directory {:?}", self.0)
+        match self.find(&path) {
+            Some(template) => template,
+            None => panic!("template {:?} not found in directories {:?}", path, self.0),
         }
     }
+
+    // First directory containing the file
+    pub fn find(&self, path: &Path) -> Option<PathBuf> {
+        self.0.iter().map(|d| d.join(path)).find(|p| p.exists())
+    }
+
+    // Path of a file inside one of the directories, relative to it
+    fn relative(&self, path: &Path) -> Option<PathBuf> {
+        self.0.iter().find_map(|d| {
+            path.strip_prefix(d)
+                .ok()
+                .or_else(|| path.strip_prefix(d.canonicalize().ok()?).ok())
+                .map(Path::to_path_buf)
+        })
+    }
 }
 
-impl From<Option<&str>> for Dir {
-    fn from(p: Option<&str>) -> Self {
+impl From<Vec<&str>> for Dir {
+    fn from(dirs: Vec<&str>) -> Self {
         let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
-        Dir(p.map_or_else(|| root.join(DEFAULT_DIR), |v| root.join(v)))
+        if dirs.is_empty() {
+            Dir(vec![root.join(DEFAULT_DIR)])
+        } else {
+            Dir(dirs.into_iter().map(|d| root.join(d)).collect())
+        }
     }
 }
 
@@ -108,41 +204,208 @@ impl From<Option<&str>> for PrintConfig {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Whitespace {
+    Preserve,
+    Suppress,
+    Standalone,
+}
+
+impl From<Option<&str>> for Whitespace {
+    fn from(s: Option<&str>) -> Self {
+        match s {
+            None | Some("preserve") => Whitespace::Preserve,
+            Some("suppress") => Whitespace::Suppress,
+            Some("standalone") => Whitespace::Standalone,
+            Some(v) => panic!(
+                "invalid value for whitespace: {}, must be `preserve`, `suppress` or `standalone`",
+                v
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct I18n<'a> {
+    pub dir: PathBuf,
+    pub default: &'a str,
+}
+
+#[derive(Debug)]
+pub struct Assets<'a> {
+    pub dir: PathBuf,
+    pub url: &'a str,
+}
+
 #[derive(Debug)]
 pub struct Config<'a> {
     dir: Dir,
+    overlays: BTreeMap<&'a str, &'a str>,
     alias: BTreeMap<&'a str, &'a str>,
+    escapers: BTreeMap<&'a str, &'a str>,
+    mime: BTreeMap<&'a str, &'a str>,
+    pub i18n: Option<I18n<'a>>,
+    pub assets: Option<Assets<'a>>,
     pub print_override: PrintConfig,
     pub debug: PrintOption<'a>,
+    pub whitespace: Whitespace,
+    pub trim_trailing: bool,
+    pub minify: bool,
+    pub warn_unused: bool,
+    pub check_fields: bool,
 }
 
 impl<'a> Config<'a> {
     pub fn new(s: &str) -> Config {
         let raw: RawConfig =
             toml::from_str(&s).expect(&format!("invalid TOML in {}", CONFIG_FILE_NAME));
-        let (dir, print) = raw.main.map(|x| (x.dir, x.debug)).unwrap_or((None, None));
+        let (dir, dirs, print, whitespace, trim_trailing, minify, warn_unused, check_fields) = raw
+            .main
+            .map(|x| {
+                (
+                    x.dir,
+                    x.dirs,
+                    x.debug,
+                    x.whitespace,
+                    x.trim_trailing,
+                    x.minify,
+                    x.warn_unused,
+                    x.check_fields,
+                )
+            })
+            .unwrap_or((None, None, None, None, None, None, None, None));
+        let dirs = match (dir, dirs) {
+            (Some(_), Some(_)) => {
+                panic!("`dir` cannot be used with `dirs` in {}", CONFIG_FILE_NAME)
+            }
+            (dir, dirs) => dirs.unwrap_or_else(|| dir.into_iter().collect()),
+        };
 
         Config {
-            dir: Dir::from(dir),
+            dir: Dir::from(dirs),
+            overlays: raw.overlays.unwrap_or_default(),
             print_override: PrintConfig::from(print),
             debug: raw.debug.unwrap_or_default(),
             alias: raw.partials.unwrap_or(BTreeMap::new()),
+            escapers: raw.escapers.unwrap_or_default(),
+            mime: raw.mime.unwrap_or_default(),
+            i18n: raw.i18n.map(|i| I18n {
+                dir: PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap())
+                    .join(i.dir.unwrap_or(DEFAULT_I18N_DIR)),
+                default: i.default,
+            }),
+            assets: raw.assets.map(|a| Assets {
+                dir: PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap())
+                    .join(a.dir.unwrap_or(DEFAULT_ASSETS_DIR)),
+                url: a.url.unwrap_or(DEFAULT_ASSETS_URL),
+            }),
+            whitespace: Whitespace::from(whitespace),
+            trim_trailing: trim_trailing.unwrap_or(true),
+            minify: minify.unwrap_or(false),
+            warn_unused: warn_unused.unwrap_or(false),
+            check_fields: check_fields.unwrap_or(false),
         }
     }
 
-    pub fn get_dir(&self) -> &PathBuf {
+    pub fn get_escaper(&self, ext: &str) -> Option<&str> {
+        self.escapers.get(ext).copied()
+    }
+
+    pub fn get_mime(&self, ext: &str) -> Option<&str> {
+        self.mime.get(ext).copied()
+    }
+
+    pub fn get_dirs(&self) -> &[PathBuf] {
         &self.dir.0
     }
 
-    pub fn get_template(&self, ident: &str) -> (PathBuf, String) {
+    #[deprecated(note = "templates can be in several directories, use `get_dirs`")]
+    pub fn get_dir(&self) -> &PathBuf {
+        &self.dir.0[0]
+    }
+
+    // Features with a template directory in `[overlays]`
+    pub fn get_overlays(&self) -> Vec<&'a str> {
+        self.overlays.keys().copied().collect()
+    }
+
+    // Directories of the enabled overlays first, in the order of the features
+    pub fn with_overlays(mut self, features: &[&str]) -> Self {
+        let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+        let mut dirs: Vec<PathBuf> = self
+            .overlays
+            .iter()
+            .filter(|(f, _)| features.contains(f))
+            .map(|(_, d)| root.join(d))
+            .collect();
+        dirs.append(&mut self.dir.0);
+        self.dir = Dir(dirs);
+        self
+    }
+
+    // Every template directory, with the ones of all the overlays
+    pub fn get_all_dirs(&self) -> Vec<PathBuf> {
+        let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+        let mut dirs: Vec<PathBuf> = self.overlays.values().map(|d| root.join(d)).collect();
+        dirs.extend(self.dir.0.iter().cloned());
+        dirs
+    }
+
+    // File in the first template directory containing it
+    pub fn find_file(&self, path: &str) -> Option<PathBuf> {
+        self.dir.find(Path::new(path))
+    }
+
+    pub fn get_template(&self, ident: &str, trim: bool) -> (PathBuf, String) {
         let path = self.dir.get_template(PathBuf::from(ident));
-        let src = get_source(path.as_path());
+        let src = get_source(path.as_path(), trim);
         (path, src)
     }
 
-    pub fn get_partial(&self, parent: &Path, ident: &str) -> (PathBuf, String) {
+    // Locales of the templates matching a path with `{locale}`, e.g. `{locale}/terms.html`
+    // or `terms.{locale}.html`
+    pub fn get_locales(&self, path: &str) -> Vec<String> {
+        let at = path.find(LOCALE).expect("path with {locale}");
+        let (before, after) = (&path[..at], &path[at + LOCALE.len()..]);
+        let (parent, prefix) = before.split_at(before.rfind('/').map_or(0, |j| j + 1));
+        let (suffix, rest) = after.split_at(after.find('/').unwrap_or(after.len()));
+
+        let dirs: Vec<PathBuf> = self.dir.0.iter().map(|d| d.join(parent)).collect();
+        let entries: Vec<_> = dirs.iter().filter_map(|d| fs::read_dir(d).ok()).collect();
+        if entries.is_empty() {
+            panic!("template directory not found: {:?}", dirs);
+        }
+        let mut locales: Vec<String> = entries
+            .into_iter()
+            .flatten()
+            .filter_map(|e| {
+                let path = e.expect("valid directory entry").path();
+                let name = path.file_name()?.to_str()?;
+                if name.len() <= prefix.len() + suffix.len()
+                    || !name.starts_with(prefix)
+                    || !name.ends_with(suffix)
+                {
+                    return None;
+                }
+
+                let locale = &name[prefix.len()..name.len() - suffix.len()];
+                let template = path.parent()?.join([prefix, locale, after].join(""));
+                if template.is_file() && (rest.is_empty() || path.is_dir()) {
+                    Some(locale.to_owned())
+                } else {
+                    None
+                }
+            })
+            .collect();
+        locales.sort();
+        locales.dedup();
+
+        locales
+    }
+
+    pub fn get_partial(&self, parent: &Path, ident: &str, trim: bool) -> (PathBuf, String) {
         let path = self.resolve_partial(parent, ident);
-        let src = get_source(path.as_path());
+        let src = get_source(path.as_path(), trim);
         (path, src)
     }
 
@@ -171,8 +434,17 @@ impl<'a> Config<'a> {
         } else {
             let mut parent = parent.to_owned();
             parent.pop();
-            parent.push(buf);
-            parent.canonicalize().expect("valid path to partial")
+            // first match of the same relative path in the template directories
+            match self
+                .dir
+                .relative(&parent)
+                .and_then(|rel| self.dir.find(&rel.join(&buf)))
+            {
+                Some(partial) => partial,
+                None => parent.join(buf),
+            }
+            .canonicalize()
+            .expect("valid path to partial")
         }
     }
 }
@@ -185,6 +457,16 @@ struct RawConfig<'a> {
     debug: Option<PrintOption<'a>>,
     #[serde(borrow)]
     partials: Option<BTreeMap<&'a str, &'a str>>,
+    #[serde(borrow)]
+    escapers: Option<BTreeMap<&'a str, &'a str>>,
+    #[serde(borrow)]
+    mime: Option<BTreeMap<&'a str, &'a str>>,
+    #[serde(borrow)]
+    i18n: Option<RawI18n<'a>>,
+    #[serde(borrow)]
+    assets: Option<RawAssets<'a>>,
+    #[serde(borrow)]
+    overlays: Option<BTreeMap<&'a str, &'a str>>,
 }
 
 #[derive(Deserialize)]
@@ -192,7 +474,31 @@ struct Main<'a> {
     #[serde(borrow)]
     dir: Option<&'a str>,
     #[serde(borrow)]
+    dirs: Option<Vec<&'a str>>,
+    #[serde(borrow)]
     debug: Option<&'a str>,
+    #[serde(borrow)]
+    whitespace: Option<&'a str>,
+    trim_trailing: Option<bool>,
+    minify: Option<bool>,
+    warn_unused: Option<bool>,
+    check_fields: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct RawI18n<'a> {
+    #[serde(borrow)]
+    dir: Option<&'a str>,
+    #[serde(borrow)]
+    default: &'a str,
+}
+
+#[derive(Deserialize)]
+struct RawAssets<'a> {
+    #[serde(borrow)]
+    dir: Option<&'a str>,
+    #[serde(borrow)]
+    url: Option<&'a str>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -226,9 +532,10 @@ pub fn config_file_path() -> PathBuf {
     PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join(CONFIG_FILE_NAME)
 }
 
-fn get_source(path: &Path) -> String {
+fn get_source(path: &Path, trim: bool) -> String {
     match fs::read_to_string(path) {
         Err(_) => panic!("unable to open template file '{:?}'", path),
+        Ok(source) if !trim => source,
         Ok(mut source) => match source
             .as_bytes()
             .iter()
@@ -252,3 +559,7 @@ fn get_source(path: &Path) -> String {
 
 static CONFIG_FILE_NAME: &str = "wearte.toml";
 static DEFAULT_DIR: &str = "templates";
+pub static LOCALE: &str = "{locale}";
+static DEFAULT_I18N_DIR: &str = "locales";
+static DEFAULT_ASSETS_DIR: &str = "static";
+static DEFAULT_ASSETS_URL: &str = "/static/";
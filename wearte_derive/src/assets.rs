@@ -0,0 +1,69 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256, Sha384};
+
+use std::fs;
+
+use wearte_config::Assets;
+
+// hexadecimal digits of the content hash in the file name
+const HASH_LEN: usize = 8;
+
+// Fingerprinted URL of an asset, `app.css` is `{url}app.3f9a1c2b.css`
+pub(crate) fn url(assets: &Assets, path: &str) -> String {
+    let digest = Sha256::digest(read(assets, path));
+    let hash: String = digest
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>()[..HASH_LEN]
+        .to_owned();
+
+    format!(
+        "{}/{}",
+        assets.url.trim_end_matches('/'),
+        fingerprint(path.trim_start_matches('/'), &hash)
+    )
+}
+
+// Subresource Integrity value of an asset, the `integrity` attribute
+pub(crate) fn integrity(assets: &Assets, path: &str) -> String {
+    format!(
+        "sha384-{}",
+        STANDARD.encode(Sha384::digest(read(assets, path)))
+    )
+}
+
+fn read(assets: &Assets, path: &str) -> Vec<u8> {
+    let file = assets.dir.join(path.trim_start_matches('/'));
+    fs::read(&file).unwrap_or_else(|_| panic!("asset not found: {:?}", file))
+}
+
+// hash before the extension of the file name
+fn fingerprint(path: &str, hash: &str) -> String {
+    let name = path.rfind('/').map_or(0, |i| i + 1);
+    match path[name..].rfind('.').filter(|i| 0 < *i) {
+        Some(dot) => {
+            let (stem, ext) = path.split_at(name + dot);
+            format!("{}.{}{}", stem, hash, ext)
+        }
+        None => format!("{}.{}", path, hash),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        assert_eq!(fingerprint("app.css", "3f9a1c2b"), "app.3f9a1c2b.css");
+        assert_eq!(
+            fingerprint("js/app.min.js", "3f9a1c2b"),
+            "js/app.min.3f9a1c2b.js"
+        );
+        assert_eq!(
+            fingerprint("v1.2/LICENSE", "3f9a1c2b"),
+            "v1.2/LICENSE.3f9a1c2b"
+        );
+        assert_eq!(fingerprint(".htaccess", "3f9a1c2b"), ".htaccess.3f9a1c2b");
+    }
+}
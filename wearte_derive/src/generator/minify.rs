@@ -0,0 +1,268 @@
+// HTML minification of template literals at compile time
+//
+// Literals are given in document order and the state is kept between them, so an
+// expression inside a tag, an attribute value or a `<pre>` doesn't lose the context.
+// Expression output is never touched.
+//
+// - Whitespace with a line break between two tags is removed when one of them is a
+//   block-level element, it would render as a space between inline ones. Any other
+//   whitespace run is collapsed to a single space. The edges of a literal may be
+//   next to an expression, so whitespace there is collapsed too
+// - Comments are removed, except conditional comments `<!--[if ...]>`
+// - Quotes of attribute values made only of `[a-zA-Z0-9-_.:]` are removed
+// - `<pre>`, `<textarea>`, `<script>` and `<style>` contents are left as is
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Text,
+    // inside a tag, with the name of the raw text element it opens
+    Tag(Option<&'static str>),
+    Quoted(u8, Option<&'static str>),
+    Raw(&'static str),
+    Comment,
+}
+
+pub(super) struct Minifier {
+    state: State,
+    // the last tag is of a block-level element
+    block: bool,
+}
+
+impl Minifier {
+    pub fn new() -> Minifier {
+        Minifier {
+            state: State::Text,
+            block: false,
+        }
+    }
+
+    pub fn minify(&mut self, src: &str) -> String {
+        use self::State::*;
+
+        let b = src.as_bytes();
+        let len = b.len();
+        let mut out = String::with_capacity(len);
+        let mut i = 0;
+        while i < len {
+            match self.state {
+                Text => {
+                    if b[i].is_ascii_whitespace() {
+                        let start = i;
+                        i = skip_ws(b, i);
+                        let tag_before = start != 0 && b[start - 1] == b'>';
+                        let tag_after = i != len && b[i] == b'<';
+                        let block = self.block || is_block(&b[i..]);
+                        if !(src[start..i].contains('\n') && tag_before && tag_after && block) {
+                            out.push(' ');
+                        }
+                    } else if src[i..].starts_with("<!--") && !src[i..].starts_with("<!--[") {
+                        self.state = Comment;
+                        i += 4;
+                    } else if b[i] == b'<'
+                        && i + 1 < len
+                        && (b[i + 1].is_ascii_alphabetic() || b[i + 1] == b'/' || b[i + 1] == b'!')
+                    {
+                        let name = &src[i + 1..i + 1 + tag_name_len(&b[i + 1..])];
+                        self.block = is_block(&b[i..]);
+                        self.state = Tag(RAW_ELEMENTS
+                            .iter()
+                            .find(|e| e.eq_ignore_ascii_case(name))
+                            .cloned());
+                        out.push('<');
+                        i += 1;
+                    } else {
+                        i = push_char(src, i, &mut out);
+                    }
+                }
+                Comment => match src[i..].find("-->") {
+                    Some(j) => {
+                        self.state = Text;
+                        i += j + 3;
+                    }
+                    None => i = len,
+                },
+                Raw(name) => match find_close(b, i, name) {
+                    Some(j) => {
+                        out.push_str(&src[i..j]);
+                        self.block = is_block(&b[j..]);
+                        self.state = Tag(None);
+                        i = j;
+                    }
+                    None => {
+                        out.push_str(&src[i..]);
+                        i = len;
+                    }
+                },
+                Tag(raw) => match b[i] {
+                    c if c.is_ascii_whitespace() => {
+                        i = skip_ws(b, i);
+                        if i == len || b[i] != b'>' {
+                            out.push(' ');
+                        }
+                    }
+                    b'>' => {
+                        self.state = raw.map_or(Text, Raw);
+                        out.push('>');
+                        i += 1;
+                    }
+                    q @ b'"' | q @ b'\'' => {
+                        if out.ends_with('=') {
+                            if let Some(j) = src[i + 1..].find(q as char).map(|j| i + 1 + j) {
+                                let value = &src[i + 1..j];
+                                let ends = match b.get(j + 1) {
+                                    Some(n) => *n == b'>' || n.is_ascii_whitespace(),
+                                    None => false,
+                                };
+                                if ends && !value.is_empty() && value.bytes().all(is_unquoted) {
+                                    out.push_str(value);
+                                    i = j + 1;
+                                    continue;
+                                }
+                            }
+                        }
+                        self.state = Quoted(q, raw);
+                        out.push(q as char);
+                        i += 1;
+                    }
+                    _ => i = push_char(src, i, &mut out),
+                },
+                Quoted(q, raw) => match src[i..].find(q as char) {
+                    Some(j) => {
+                        out.push_str(&src[i..=i + j]);
+                        self.state = Tag(raw);
+                        i += j + 1;
+                    }
+                    None => {
+                        out.push_str(&src[i..]);
+                        i = len;
+                    }
+                },
+            }
+        }
+
+        out
+    }
+}
+
+fn skip_ws(b: &[u8], mut i: usize) -> usize {
+    while i < b.len() && b[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+fn push_char(src: &str, i: usize, out: &mut String) -> usize {
+    let c = src[i..].chars().next().unwrap();
+    out.push(c);
+    i + c.len_utf8()
+}
+
+fn tag_name_len(b: &[u8]) -> usize {
+    b.iter().take_while(|c| c.is_ascii_alphanumeric()).count()
+}
+
+// The tag at the start of `b` is of a block-level element, a doctype or a comment
+fn is_block(b: &[u8]) -> bool {
+    let b = match b {
+        [b'<', b'!', ..] => return true,
+        [b'<', b'/', rest @ ..] | [b'<', rest @ ..] => rest,
+        _ => return false,
+    };
+    let name = &b[..tag_name_len(b)];
+    BLOCK_ELEMENTS
+        .split_whitespace()
+        .any(|e| e.as_bytes().eq_ignore_ascii_case(name))
+}
+
+// Position of the closing tag `</name` case insensitive
+fn find_close(b: &[u8], from: usize, name: &str) -> Option<usize> {
+    (from..b.len()).find(|&i| {
+        b[i..].starts_with(b"</")
+            && b.len() >= i + 2 + name.len()
+            && b[i + 2..i + 2 + name.len()].eq_ignore_ascii_case(name.as_bytes())
+    })
+}
+
+#[inline]
+fn is_unquoted(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'-' || c == b'_' || c == b'.' || c == b':'
+}
+
+static RAW_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+// block-level elements, and the ones of the document head
+static BLOCK_ELEMENTS: &str = "address article aside base blockquote body br caption col \
+     colgroup dd details dialog div dl dt fieldset figcaption figure footer form h1 h2 h3 h4 \
+     h5 h6 head header hr html li link main menu meta nav noscript ol optgroup option p pre \
+     script section style summary table tbody td template tfoot th thead title tr ul";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn minify(chunks: &[&str]) -> Vec<String> {
+        let mut m = Minifier::new();
+        chunks.iter().map(|c| m.minify(c)).collect()
+    }
+
+    #[test]
+    fn test_whitespace() {
+        assert_eq!(
+            minify(&["<ul>\n    <li>a  b</li>\n    <li> c </li>\n</ul>\n"]),
+            vec!["<ul><li>a b</li><li> c </li></ul> "]
+        );
+        assert_eq!(minify(&["<p>\n  ", "\n</p>"]), vec!["<p> ", " </p>"]);
+        assert_eq!(minify(&["a\n  b", "  "]), vec!["a b", " "]);
+        // inline elements keep a space
+        assert_eq!(
+            minify(&["<b>Hello</b>\n<i>world</i>\n<div>\n<span>a</span>\n</div>"]),
+            vec!["<b>Hello</b> <i>world</i><div><span>a</span></div>"]
+        );
+        // next to an expression `<i>{{ a }}\n<b>{{ b }}</b>`
+        assert_eq!(
+            minify(&["<i>", "\n<b>", "</b>\n"]),
+            vec!["<i>", " <b>", "</b> "]
+        );
+    }
+
+    #[test]
+    fn test_comment() {
+        assert_eq!(
+            minify(&["a<!-- b\n-->c<!--[if IE]><p><![endif]-->"]),
+            vec!["ac<!--[if IE]><p><![endif]-->"]
+        );
+        assert_eq!(minify(&["a<!-- b", "c -->d"]), vec!["a", "d"]);
+    }
+
+    #[test]
+    fn test_attributes() {
+        assert_eq!(
+            minify(&["<a  href=\"/x\"\n  class=\"a-b\" id='c' title=\"\" >"]),
+            vec!["<a href=\"/x\" class=a-b id=c title=\"\">"]
+        );
+        assert_eq!(
+            minify(&["<div class=\"", "  b\">", "</div>"]),
+            vec!["<div class=\"", "  b\">", "</div>"]
+        );
+        assert_eq!(
+            minify(&["<input value=\"a\"/>"]),
+            vec!["<input value=\"a\"/>"]
+        );
+    }
+
+    #[test]
+    fn test_raw() {
+        assert_eq!(
+            minify(&["<pre>\n  a\n  <b> c </b>\n</PRE>\n<p>"]),
+            vec!["<pre>\n  a\n  <b> c </b>\n</PRE><p>"]
+        );
+        assert_eq!(
+            minify(&["<script type=\"x\">\n  if (a < b) {", "}\n</script>\n"]),
+            vec!["<script type=x>\n  if (a < b) {", "}\n</script> "]
+        );
+        assert_eq!(
+            minify(&["<textarea>  a\n", "  </textarea>"]),
+            vec!["<textarea>  a\n", "  </textarea>"]
+        );
+    }
+}
@@ -1,28 +1,331 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
 use mime_guess::get_mime_type_str;
 use syn::{self, visit::Visit};
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Write},
-    mem,
+    fs, mem,
     path::PathBuf,
+    rc::Rc,
     str,
 };
 
-use wearte_config::Config;
+use wearte_config::{Config, Whitespace};
 
+mod minify;
 mod validator;
 mod visit_derive;
 mod visit_each;
 mod visits;
 
-pub(crate) use self::visit_derive::{visit_derive, Print, Struct};
+use self::minify::Minifier;
+pub(crate) use self::visit_derive::{
+    visit_derive, visit_enum, visit_locales, Enum, Locales, Print, Struct, Variant, HTML_ESCAPER,
+};
 use self::visit_each::find_loop_var;
 
-use crate::parser::{Helper, Node, Ws};
+use crate::assets;
+use crate::i18n::{self, parts, Catalog, Catalogs, Part};
+use crate::parser::{Asset, Encoding, Helper, Node, Params, Ws};
+
+pub(crate) fn generate<'a>(
+    c: &Config,
+    s: &Struct,
+    ctx: &'a Parsed<'a>,
+    sources: &'a SourceMap,
+) -> String {
+    Generator::new(c, s, ctx, sources).build()
+}
+
+pub(crate) fn generate_enum<'a>(
+    c: &Config,
+    e: &Enum,
+    ctx: &'a Parsed<'a>,
+    sources: &'a SourceMap,
+) -> String {
+    let mut display = String::new();
+    let mut render = String::new();
+    let mut mimes = vec![];
+    let mut hints = vec![];
+    let mut delegated = vec![];
+
+    for v in &e.variants {
+        let (pat, fields) = variant_pattern(v);
+        match v.template {
+            Some(ref s) => {
+                let mut g = Generator::new(c, s, ctx, sources);
+                if let syn::Fields::Unnamed(_) = v.fields {
+                    g.root = "_0";
+                    g.scp = vec![vec!["_0".to_owned()]];
+                }
+                g.fields = fields;
+                let nodes: &[Node] = g.ctx.get(&g.on_path).unwrap();
+
+                writeln!(display, "#[allow(unused_variables)]\n{} => {{", pat).unwrap();
+                g.display_body(nodes, &mut display);
+                display.writeln(&"Ok(())\n}");
+
+                writeln!(render, "#[allow(unused_variables)]\n{} => {{", pat).unwrap();
+                g.render_body(nodes, &mut render);
+                render.writeln(&"Ok(())\n}");
+
+                mimes.push((pat, Some(g.get_mime()), format!("{:?}", g.get_mime())));
+                let hint = g.size_hint_expr();
+                hints.push((hint.clone(), hint));
+            }
+            // the only field is a template
+            None => {
+                let ty = match v.fields {
+                    syn::Fields::Unnamed(ref f) => &f.unnamed[0].ty,
+                    _ => unreachable!(),
+                };
+
+                writeln!(display, "{} => ::std::fmt::Display::fmt(_0, _fmt),", pat).unwrap();
+                writeln!(
+                    render,
+                    "{} => ::wearte::Template::render_into(_0, _buf),",
+                    pat
+                )
+                .unwrap();
+                mimes.push((
+                    pat,
+                    None,
+                    format!("<{} as ::wearte::Template>::mime()", quote!(#ty)),
+                ));
+                delegated.push(quote!(#ty).to_string());
+                hints.push((
+                    format!("<{} as ::wearte::Template>::size_hint()", quote!(#ty)),
+                    "::wearte::Template::dyn_size_hint(_0)".to_owned(),
+                ));
+            }
+        }
+    }
+
+    // a single mime type, the variants with a template must agree, there is one
+    // at least
+    let mut known = mimes.iter().filter_map(|(_, m, _)| m.as_ref());
+    let first = known.next().unwrap();
+    if let Some(other) = known.find(|m| *m != first) {
+        panic!(
+            "variants of {} have different mime types, {} and {}; set the `mime` attribute \
+             of the variants",
+            e.ident, first, other
+        );
+    }
+    let mime = format!("{:?}", first);
+
+    // and the delegated ones at compile time, when they are derived templates
+    let mut checks = String::new();
+    for ty in &delegated {
+        writeln!(
+            checks,
+            "const {{ assert!(::wearte::same_mime(Some({}), ::wearte::Child::<{}>::MIME), {:?}) }};",
+            mime,
+            ty,
+            format!(
+                "variants of {} have different mime types; set the `mime` attribute of the \
+                 variants",
+                e.ident
+            )
+        )
+        .unwrap();
+    }
+    if !checks.is_empty() {
+        checks = format!(
+            "{{\n#[allow(unused_imports)]\nuse ::wearte::ChildMime as _;\n{}}}\n",
+            checks
+        );
+    }
+
+    let mut buf = String::new();
+    e.implement_head("::std::fmt::Display", &mut buf);
+    buf.writeln(&"fn fmt(&self, _fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {");
+    buf.write(&checks);
+    buf.writeln(&"match self {");
+    buf.writeln(&display);
+    buf.writeln(&"}\n}\n}");
+
+    e.implement_head("::wearte::Template", &mut buf);
+    buf.writeln(
+        &"fn render_into<__WearteB: ::wearte::Buffer + ?Sized>(&self, _buf: &mut __WearteB) \
+          -> ::std::fmt::Result {",
+    );
+    buf.writeln(&"_buf.reserve(::wearte::Template::dyn_size_hint(self));");
+    buf.write(&checks);
+    buf.writeln(&"match self {");
+    buf.writeln(&render);
+    buf.writeln(&"}\n}");
+
+    buf.writeln(&"fn mime() -> &'static str {");
+    buf.writeln(&mime);
+    buf.writeln(&"}");
+
+    buf.writeln(&"fn size_hint() -> usize {");
+    buf.writeln(&"let mut hint = 0;");
+    for (hint, _) in &hints {
+        writeln!(buf, "hint = ::std::cmp::max(hint, {});", hint).unwrap();
+    }
+    buf.writeln(&"hint\n}");
+
+    buf.writeln(&"fn dyn_mime(&self) -> &'static str {");
+    buf.writeln(&"match self {");
+    for (pat, _, mime) in &mimes {
+        writeln!(buf, "#[allow(unused_variables)]\n{} => {},", pat, mime).unwrap();
+    }
+    buf.writeln(&"}\n}");
+
+    buf.writeln(&"fn dyn_size_hint(&self) -> usize {");
+    buf.writeln(&"match self {");
+    for ((pat, _, _), (_, hint)) in mimes.iter().zip(&hints) {
+        writeln!(buf, "#[allow(unused_variables)]\n{} => {},", pat, hint).unwrap();
+    }
+    buf.writeln(&"}\n}");
+    buf.writeln(&"}");
+
+    template_mime(&|t, buf| e.implement_head(t, buf), &mime, &mut buf);
+    responders(&|t, buf| e.implement_head(t, buf), &mut buf);
 
-pub(crate) fn generate(c: &Config, s: &Struct, ctx: Context) -> String {
-    Generator::new(c, s, ctx).build()
+    buf
+}
+
+pub(crate) fn generate_locales<'a>(
+    c: &Config,
+    l: &Locales,
+    ctx: &'a Parsed<'a>,
+    sources: &'a SourceMap,
+) -> String {
+    let names: Vec<&str> = l.templates.iter().map(|(l, _)| l.as_str()).collect();
+    let mut display = String::new();
+    let mut render = String::new();
+    let mut scrutinee = String::new();
+    let mut mime = None;
+    let mut hints = vec![];
+
+    // the fallback is the last arm
+    let mut order: Vec<usize> = (0..l.templates.len())
+        .filter(|i| *i != l.fallback)
+        .collect();
+    order.push(l.fallback);
+    for i in order {
+        let (locale, s) = &l.templates[i];
+        let mut g = Generator::new(c, s, ctx, sources);
+        scrutinee = g.locale_expr(s.locale.as_ref().unwrap(), &names);
+        let nodes: &[Node] = g.ctx.get(&g.on_path).unwrap();
+        let arm = if i == l.fallback {
+            "_".to_owned()
+        } else {
+            format!("Some({})", i)
+        };
+
+        writeln!(display, "// {}\n{} => {{", locale, arm).unwrap();
+        g.display_body(nodes, &mut display);
+        display.writeln(&"}");
+
+        writeln!(render, "// {}\n{} => {{", locale, arm).unwrap();
+        g.render_body(nodes, &mut render);
+        render.writeln(&"}");
+
+        match mime {
+            None => mime = Some(g.get_mime()),
+            Some(ref m) if *m != g.get_mime() => panic!(
+                "templates of {} have different mime types, {} and {}",
+                s.path.display(),
+                m,
+                g.get_mime()
+            ),
+            _ => (),
+        }
+        hints.push(g.size_hint_expr());
+    }
+
+    let s = &l.templates[l.fallback].1;
+    let mut buf = String::new();
+    s.implement_head("::std::fmt::Display", &mut buf);
+    buf.writeln(&"fn fmt(&self, _fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {");
+    writeln!(buf, "match {} {{", scrutinee).unwrap();
+    buf.writeln(&display);
+    buf.writeln(&"}\nOk(())\n}\n}");
+
+    s.implement_head("::wearte::Template", &mut buf);
+    buf.writeln(
+        &"fn render_into<__WearteB: ::wearte::Buffer + ?Sized>(&self, _buf: &mut __WearteB) \
+          -> ::std::fmt::Result {",
+    );
+    buf.writeln(&"_buf.reserve(<Self as ::wearte::Template>::size_hint());");
+    writeln!(buf, "match {} {{", scrutinee).unwrap();
+    buf.writeln(&render);
+    buf.writeln(&"}\nOk(())\n}");
+
+    let mime = format!("{:?}", mime.unwrap());
+    buf.writeln(&"fn mime() -> &'static str {");
+    buf.writeln(&mime);
+    buf.writeln(&"}");
+    buf.writeln(&"fn size_hint() -> usize {");
+    buf.writeln(&"let mut hint = 0;");
+    for hint in &hints {
+        writeln!(buf, "hint = ::std::cmp::max(hint, {});", hint).unwrap();
+    }
+    buf.writeln(&"hint\n}");
+    buf.writeln(&"}");
+
+    template_mime(&|t, buf| s.implement_head(t, buf), &mime, &mut buf);
+    responders(&|t, buf| s.implement_head(t, buf), &mut buf);
+
+    buf
+}
+
+// Match pattern of the variant and its named fields
+fn variant_pattern(v: &Variant) -> (String, Vec<String>) {
+    let ident = v.ident;
+    match v.fields {
+        syn::Fields::Named(ref f) => {
+            let fields: Vec<String> = f
+                .named
+                .iter()
+                .map(|f| f.ident.as_ref().unwrap().to_string())
+                .collect();
+            (
+                format!("Self::{} {{ {} }}", ident, fields.join(", ")),
+                fields,
+            )
+        }
+        syn::Fields::Unnamed(_) => (format!("Self::{}(_0)", ident), vec![]),
+        syn::Fields::Unit => (format!("Self::{}", ident), vec![]),
+    }
+}
+
+// MIME type at compile time, for the checks of the nested templates
+fn template_mime(implement_head: &dyn Fn(&str, &mut String), mime: &str, buf: &mut String) {
+    implement_head("::wearte::TemplateMime", buf);
+    writeln!(buf, "const MIME: &'static str = {};", mime).unwrap();
+    buf.writeln(&"}");
+}
+
+// Implementations of the framework traits enabled by the features
+fn responders(implement_head: &dyn Fn(&str, &mut String), buf: &mut String) {
+    if cfg!(feature = "actix-web") {
+        implement_head("::wearte::actix_web::Responder", buf);
+
+        buf.writeln(&"type Body = ::wearte::actix_web::BoxBody;");
+        buf.writeln(
+            &"fn respond_to(self, req: &::wearte::actix_web::HttpRequest) \
+              -> ::wearte::actix_web::HttpResponse {",
+        );
+        buf.writeln(&"::wearte::actix_web::respond(&self, req)");
+
+        buf.writeln(&"}");
+        buf.writeln(&"}");
+    }
+
+    if cfg!(feature = "axum") {
+        implement_head("::wearte::axum::IntoResponse", buf);
+
+        buf.writeln(&"fn into_response(self) -> ::wearte::axum::Response {");
+        buf.writeln(&"::wearte::axum::into_response(&self)");
+        buf.writeln(&"}");
+        buf.writeln(&"}");
+    }
 }
 
 pub(crate) trait EWrite: fmt::Write {
@@ -37,7 +340,20 @@ pub(crate) trait EWrite: fmt::Write {
 
 impl EWrite for String {}
 
+// Sources and their nodes by whitespace mode and `trim_trailing`, a partial is
+// read differently by templates with other settings
+pub(crate) type Mode = (Whitespace, bool);
+pub(crate) type SourceMap = BTreeMap<Mode, BTreeMap<PathBuf, String>>;
+pub(crate) type Parsed<'a> = BTreeMap<Mode, BTreeMap<&'a PathBuf, Vec<Node<'a>>>>;
 pub(self) type Context<'a> = &'a BTreeMap<&'a PathBuf, Vec<Node<'a>>>;
+type Sources<'a> = &'a BTreeMap<PathBuf, String>;
+// file, whitespace, parameters and body of a macro
+type MacroDef<'a> = (PathBuf, &'a (Ws, Ws), &'a Params<'a>, &'a [Node<'a>]);
+
+// Size hint of the output: literal bytes, plus `EXPR_HINT` for each expression and
+// `LOOP_HINT` times the body of each loop, taking the longest branch of conditionals
+const EXPR_HINT: usize = 16;
+const LOOP_HINT: usize = 8;
 
 #[derive(Debug, PartialEq)]
 pub(self) enum On {
@@ -62,6 +378,18 @@ pub(self) struct Generator<'a> {
     pub(self) buf_t: String,
     // Scope stack
     pub(self) scp: Vec<Vec<String>>,
+    // context of the template, `self` or the field of a tuple variant
+    root: &'static str,
+    // fields of the enum variant bound by the match
+    fields: Vec<String>,
+    // fields of the context read by the template
+    used: BTreeSet<String>,
+    // macros being expanded
+    macros: Vec<(PathBuf, &'a str)>,
+    // sources of the templates, for the location of the errors
+    sources: Sources<'a>,
+    // message catalogs, loaded by the first translation
+    catalogs: Option<Rc<Catalogs>>,
     // On State stack
     pub(self) on: Vec<On>,
     // buffer for writable
@@ -72,27 +400,58 @@ pub(self) struct Generator<'a> {
     on_path: PathBuf,
     // heuristic based on https://github.com/lfairy/maud
     size_hint: usize,
+    // estimated output length of each open block
+    hints: Vec<usize>,
+    // size hints of the nested templates
+    children: Vec<String>,
+    // compile time checks of the MIME types of the nested templates
+    mime_checks: Vec<String>,
     // whitespace flag and buffer based on https://github.com/djc/askama
     next_ws: Option<&'a str>,
     skip_ws: bool,
+    // html minifier of literals
+    minifier: Option<Minifier>,
+    // byte output pass, `Template::render_into`
+    bytes: bool,
 }
 
 impl<'a> Generator<'a> {
-    fn new<'n>(c: &'n Config<'n>, s: &'n Struct<'n>, ctx: Context<'n>) -> Generator<'n> {
+    fn new<'n>(
+        c: &'n Config<'n>,
+        s: &'n Struct<'n>,
+        ctx: &'n Parsed<'n>,
+        sources: &'n SourceMap,
+    ) -> Generator<'n> {
+        let mode = (s.whitespace, s.trim_trailing);
         Generator {
             c,
             s,
-            ctx,
+            ctx: &ctx[&mode],
             buf_t: String::new(),
             buf_w: vec![],
             next_ws: None,
             on: vec![],
             on_path: s.path.clone(),
             scp: vec![vec!["self".to_string()]],
+            root: "self",
+            fields: vec![],
+            used: BTreeSet::new(),
+            macros: vec![],
+            sources: &sources[&mode],
+            catalogs: None,
             skip_ws: false,
             will_wrap: true,
             wrapped: true,
             size_hint: 0,
+            hints: vec![0],
+            children: vec![],
+            mime_checks: vec![],
+            minifier: if s.minify {
+                Some(Minifier::new())
+            } else {
+                None
+            },
+            bytes: false,
         }
     }
 
@@ -102,80 +461,176 @@ impl<'a> Generator<'a> {
         let nodes: &[Node] = self.ctx.get(&self.on_path).unwrap();
         self.display(nodes, &mut buf);
 
-        debug_assert_ne!(self.size_hint, 0);
-        self.template(&mut buf);
+        let mut render = String::new();
+        self.render_into(nodes, &mut render);
 
-        if cfg!(feature = "actix-web") {
-            self.responder(&mut buf);
-        }
+        self.template(&render, &mut buf);
+
+        responders(&|t, buf| self.s.implement_head(t, buf), &mut buf);
 
         buf
     }
 
-    fn get_mime(&mut self) -> &str {
-        let ext = if self.s.wrapped {
-            match self.s.path.extension() {
-                Some(s) => s.to_str().unwrap(),
-                None => "txt",
-            }
+    // `mime` attribute, `[mime]` table of the config, or by extension
+    fn get_mime(&mut self) -> String {
+        let path_ext = match self.s.path.extension() {
+            Some(s) => s.to_str().unwrap(),
+            None => "txt",
+        };
+        let ext = if self.s.wrapped || self.s.escaper != HTML_ESCAPER {
+            path_ext
         } else {
             "html"
         };
 
-        get_mime_type_str(ext).expect("valid mime ext")
+        let mime = match (&self.s.mime, self.c.get_mime(path_ext)) {
+            (Some(mime), _) => mime.as_str(),
+            (None, Some(mime)) => mime,
+            (None, None) => get_mime_type_str(ext).unwrap_or_else(|| {
+                panic!(
+                    "unknown mime type of extension '{}', set it with the `mime` attribute \
+                     or in the `[mime]` table of the config",
+                    ext
+                )
+            }),
+        };
+
+        let mime = match &self.s.charset {
+            Some(charset) => format!("{}; charset={}", mime, charset),
+            None => mime.to_owned(),
+        };
+        validator::mime(&mime, &self.s.path);
+
+        mime
     }
 
-    fn template(&mut self, buf: &mut String) {
+    fn template(&mut self, render: &str, buf: &mut String) {
+        // last output sizes shared by all the instances of the type
+        if self.s.adaptive {
+            buf.writeln(&"const _: () = {");
+            writeln!(
+                buf,
+                "static _SIZE_HINT: ::wearte::SizeHint = ::wearte::SizeHint::new({});",
+                self.size_hint
+            )
+            .unwrap();
+        }
+
         self.s.implement_head("::wearte::Template", buf);
 
+        buf.writeln(&render);
+
         buf.writeln(&"fn mime() -> &'static str {");
         writeln!(buf, "{:?}", self.get_mime()).unwrap();
         buf.writeln(&"}");
         buf.writeln(&"fn size_hint() -> usize {");
-        buf.writeln(&self.size_hint);
+        if self.s.adaptive {
+            buf.writeln(&"_SIZE_HINT.get()");
+            buf.writeln(&"}");
+            buf.writeln(&"fn record_size(&self, len: usize) {");
+            buf.writeln(&"_SIZE_HINT.record(len)");
+        } else {
+            buf.writeln(&self.size_hint_expr());
+        }
         buf.writeln(&"}");
         buf.writeln(&"}");
+
+        let mime = format!("{:?}", self.get_mime());
+        template_mime(&|t, buf| self.s.implement_head(t, buf), &mime, buf);
+
+        if self.s.adaptive {
+            buf.writeln(&"};");
+        }
     }
 
     fn display(&mut self, nodes: &'a [Node], buf: &mut String) {
         self.s.implement_head("::std::fmt::Display", buf);
 
         buf.writeln(&"fn fmt(&self, _fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {");
+        self.display_body(nodes, buf);
+        buf.writeln(&quote!(Ok(())));
 
-        let last = buf.len();
+        buf.writeln(&"}");
+        buf.writeln(&"}");
+    }
+
+    fn display_body(&mut self, nodes: &'a [Node], buf: &mut String) {
+        if !self.s.wrapped {
+            buf.writeln(&"#[allow(unused_imports)]");
+            buf.writeln(&"use ::wearte::{ViaDisplay as _, ViaRender as _, ViaTemplate as _};");
+        }
 
         self.handle(nodes, buf);
+        if !self.s.trim_trailing {
+            self.flush_ws(&(false, false));
+        }
         debug_assert_eq!(self.scp.len(), 1);
-        debug_assert_eq!(self.scp[0][0], "self");
+        debug_assert_eq!(self.scp[0][0], self.root);
         debug_assert_eq!(self.on.len(), 0);
         debug_assert_eq!(self.on_path, self.s.path);
         debug_assert!(self.will_wrap);
         self.write_buf_writable(buf);
-        self.size_hint = 1 + buf.len() - last;
+        self.write_mime_checks(buf);
+        debug_assert_eq!(self.hints.len(), 1);
+        self.size_hint = self.pop_hint();
 
-        buf.writeln(&quote!(Ok(())));
+        if self.s.warn_unused {
+            self.warn_unused(buf);
+        }
+    }
 
-        buf.writeln(&"}");
+    // Same template writing bytes, literals are appended with `extend_from_slice`
+    // and strings escaped straight into the buffer
+    fn render_into(&mut self, nodes: &'a [Node], buf: &mut String) {
+        buf.writeln(
+            &"fn render_into<__WearteB: ::wearte::Buffer + ?Sized>(&self, _buf: &mut __WearteB) \
+              -> ::std::fmt::Result {",
+        );
+        buf.writeln(&"_buf.reserve(<Self as ::wearte::Template>::size_hint());");
+        self.render_body(nodes, buf);
+        buf.writeln(&quote!(Ok(())));
         buf.writeln(&"}");
     }
 
-    fn responder(&mut self, buf: &mut String) {
-        self.s.implement_head("::wearte::actix_web::Responder", buf);
+    fn render_body(&mut self, nodes: &'a [Node], buf: &mut String) {
+        self.bytes = true;
+        self.scp = vec![vec![self.root.to_owned()]];
+        self.next_ws = None;
+        self.skip_ws = false;
+        self.hints = vec![0];
+        if self.minifier.is_some() {
+            self.minifier = Some(Minifier::new());
+        }
 
-        buf.writeln(&"type Item = ::wearte::actix_web::HttpResponse;");
-        buf.writeln(&"type Error = ::wearte::actix_web::Error;");
+        buf.writeln(&"#[allow(unused_imports)]");
         buf.writeln(
-            &"fn respond_to<S>(self, _req: &::wearte::actix_web::HttpRequest<S>) \
-              -> ::std::result::Result<Self::Item, Self::Error> {",
+            &"use ::wearte::{ViaDisplay as _, ViaRender as _, ViaStr as _, ViaTemplate as _};",
         );
 
-        buf.writeln(
-            &"self.call()
-                .map(|s| Self::Item::Ok().content_type(Self::mime()).body(s))
-                .map_err(|_| ::wearte::actix_web::ErrorInternalServerError(\"Template parsing error\"))"
-        );
+        self.handle(nodes, buf);
+        if !self.s.trim_trailing {
+            self.flush_ws(&(false, false));
+        }
+        self.write_buf_writable(buf);
+        self.write_mime_checks(buf);
 
-        buf.writeln(&"}");
+        self.bytes = false;
+    }
+
+    // evaluated when the function is monomorphized, a compile error
+    fn write_mime_checks(&mut self, buf: &mut String) {
+        if self.mime_checks.is_empty() {
+            return;
+        }
+
+        self.mime_checks.sort();
+        self.mime_checks.dedup();
+        buf.writeln(&"{");
+        buf.writeln(&"#[allow(unused_imports)]");
+        buf.writeln(&"use ::wearte::ChildMime as _;");
+        for check in &self.mime_checks {
+            buf.writeln(check);
+        }
         buf.writeln(&"}");
     }
 
@@ -194,6 +649,7 @@ impl<'a> Generator<'a> {
                     validator::expression(expr);
 
                     self.visit_expr(expr);
+                    self.child_hint(expr, false);
                     self.handle_ws(ws);
                     self.buf_w.push(Writable::Expr(
                         mem::replace(&mut self.buf_t, String::new()),
@@ -205,6 +661,7 @@ impl<'a> Generator<'a> {
 
                     self.wrapped = false;
                     self.visit_expr(expr);
+                    self.child_hint(expr, !self.wrapped);
                     self.handle_ws(ws);
                     self.buf_w.push(Writable::Expr(
                         mem::replace(&mut self.buf_t, String::new()),
@@ -214,6 +671,13 @@ impl<'a> Generator<'a> {
                 Node::Lit(l, lit, r) => self.visit_lit(l, lit, r),
                 Node::Helper(h) => self.visit_helper(buf, h),
                 Node::Partial(ws, path, expr) => self.visit_partial(buf, ws, path, expr),
+                Node::Call(ws, alias, name, args) => self.visit_call(buf, ws, *alias, name, args),
+                Node::Trans(ws, key, args) => self.visit_trans(buf, ws, key, args),
+                Node::Asset(ws, kind, path) => self.visit_asset(buf, ws, *kind, path),
+                Node::Embed(ws, path, encoding) => self.visit_embed(buf, ws, path, *encoding),
+                // definitions don't write anything
+                Node::Macro(ws, ..) => self.handle_ws(&((ws.0).0, (ws.1).1)),
+                Node::Import(ws, ..) => self.handle_ws(ws),
                 Node::Comment(..) => self.skip_ws(),
                 Node::Raw(ws, l, v, r) => {
                     self.handle_ws(&ws.0);
@@ -277,12 +741,15 @@ impl<'a> Generator<'a> {
         )
         .unwrap();
 
+        self.hints.push(0);
         self.scp.push(vec![]);
         self.handle(nodes, buf);
         self.scp.pop();
 
         self.handle_ws(&ws.1);
         self.write_buf_writable(buf);
+        let hint = self.pop_hint();
+        self.add_hint(hint);
         buf.writeln(&"}");
     }
 
@@ -368,11 +835,18 @@ impl<'a> Generator<'a> {
         };
         self.on.push(On::Each(id));
         self.scp.push(ctx);
+        self.hints.push(0);
 
         self.handle(nodes, buf);
         self.handle_ws(&ws.1);
         self.write_buf_writable(buf);
 
+        let hint = self.pop_hint();
+        self.add_hint(hint * LOOP_HINT);
+        if self.bytes {
+            buf.writeln(&"_buf.chunk_boundary()?;");
+        }
+
         self.scp.pop();
         self.on.pop();
         buf.writeln(&"}");
@@ -390,6 +864,7 @@ impl<'a> Generator<'a> {
         self.handle_ws(&pws.0);
         self.write_buf_writable(buf);
 
+        self.hints.push(0);
         self.scp.push(vec![]);
         self.visit_expr(cond);
         writeln!(
@@ -402,11 +877,14 @@ impl<'a> Generator<'a> {
         self.handle(block, buf);
         self.scp.pop();
 
+        let mut hint = 0;
         for (ws, cond, block) in ifs {
             validator::ifs(cond);
 
-            self.handle_ws(&ws);
+            self.handle_ws(ws);
             self.write_buf_writable(buf);
+            hint = hint.max(self.pop_hint());
+            self.hints.push(0);
 
             self.scp.push(vec![]);
             self.visit_expr(cond);
@@ -424,6 +902,8 @@ impl<'a> Generator<'a> {
         if let Some((ws, els)) = els {
             self.handle_ws(ws);
             self.write_buf_writable(buf);
+            hint = hint.max(self.pop_hint());
+            self.hints.push(0);
 
             buf.writeln(&"} else {");
 
@@ -434,6 +914,8 @@ impl<'a> Generator<'a> {
 
         self.handle_ws(&pws.1);
         self.write_buf_writable(buf);
+        hint = hint.max(self.pop_hint());
+        self.add_hint(hint);
         buf.writeln(&"}");
     }
 
@@ -463,11 +945,311 @@ impl<'a> Generator<'a> {
             self.scp = parent;
         }
 
+        // trailing whitespace of the partial file, only with `trim_trailing = false`
+        self.flush_ws(&(false, false));
+
         self.prepare_ws(ws);
 
         self.on_path = p;
     }
 
+    // Macros are expanded in a block with their arguments bound to the parameters,
+    // the body only sees the parameters and the root context
+    fn visit_call(
+        &mut self,
+        buf: &mut String,
+        ws: &Ws,
+        alias: Option<&str>,
+        name: &'a str,
+        args: &'a [syn::Expr],
+    ) {
+        let (path, def_ws, params, nodes) = self.find_macro(alias, name);
+        if params.len() != args.len() {
+            panic!(
+                "macro `{}` takes {} arguments but {} were given",
+                name,
+                params.len(),
+                args.len()
+            );
+        }
+        if self.macros.iter().any(|(p, n)| *p == path && *n == name) {
+            panic!("recursive call of macro `{}`", name);
+        }
+
+        self.flush_ws(ws);
+        self.write_buf_writable(buf);
+
+        buf.writeln(&"{");
+        if !params.is_empty() {
+            let mut names = String::new();
+            let mut types = String::new();
+            let mut values = String::new();
+            for ((param, ty), arg) in params.iter().zip(args) {
+                validator::expression(arg);
+                self.visit_expr(arg);
+                let arg = mem::take(&mut self.buf_t);
+                write!(names, "{},", param).unwrap();
+                // untyped parameters borrow the argument
+                match ty {
+                    Some(ty) => {
+                        write!(types, "{},", quote!(#ty)).unwrap();
+                        write!(values, "({}),", arg).unwrap();
+                    }
+                    None => {
+                        types.push_str("_,");
+                        write!(values, "&({}),", arg).unwrap();
+                    }
+                }
+            }
+            writeln!(buf, "let ({}): ({}) = ({});", names, types, values).unwrap();
+        }
+
+        let root = self.scp[0][0].clone();
+        let scp = mem::replace(
+            &mut self.scp,
+            vec![
+                vec![root],
+                params.iter().map(|(p, _)| (*p).to_owned()).collect(),
+            ],
+        );
+        let on = mem::take(&mut self.on);
+        let parent = mem::replace(&mut self.on_path, path.clone());
+        self.macros.push((path, name));
+
+        self.prepare_ws(&(false, (def_ws.0).1));
+        self.handle(nodes, buf);
+        self.flush_ws(&((def_ws.1).0, false));
+        self.write_buf_writable(buf);
+        buf.writeln(&"}");
+
+        self.macros.pop();
+        self.on_path = parent;
+        self.on = on;
+        self.scp = scp;
+
+        self.prepare_ws(ws);
+    }
+
+    // Translated message, in the locale of `locale_field` when it has a catalog
+    // with the message and in the default one otherwise
+    fn visit_trans(
+        &mut self,
+        buf: &mut String,
+        ws: &Ws,
+        key: &str,
+        args: &'a [(&'a str, syn::Expr)],
+    ) {
+        let catalogs = self.catalogs();
+        let names: Vec<&str> = args.iter().map(|(name, _)| *name).collect();
+        if !self.bytes {
+            validator::message(key, &names, &catalogs, &self.on_path);
+        }
+
+        self.handle_ws(ws);
+        self.write_buf_writable(buf);
+
+        let translated: Vec<usize> = (1..catalogs.catalogs.len())
+            .filter(|i| catalogs.catalogs[*i].messages.contains_key(key))
+            .collect();
+        let locale = match self.s.locale {
+            Some(ref locale) if !translated.is_empty() => {
+                Some(self.locale_expr(locale, &catalogs.locales()))
+            }
+            _ => None,
+        };
+
+        buf.writeln(&"{");
+        if !args.is_empty() {
+            let mut values = String::new();
+            for (_, arg) in args {
+                validator::expression(arg);
+                self.visit_expr(arg);
+                write!(values, "&({}), ", mem::take(&mut self.buf_t)).unwrap();
+            }
+            writeln!(buf, "let ({},) = ({});", names.join(", "), values).unwrap();
+        }
+
+        match locale {
+            Some(locale) => {
+                writeln!(buf, "match {} {{", locale).unwrap();
+
+                let mut hint = 0;
+                for i in translated {
+                    self.hints.push(0);
+                    writeln!(buf, "Some({}) => {{", i).unwrap();
+                    self.write_message(buf, &catalogs.catalogs[i], key);
+                    buf.writeln(&"}");
+                    hint = hint.max(self.pop_hint());
+                }
+                self.hints.push(0);
+                buf.writeln(&"_ => {");
+                self.write_message(buf, catalogs.default(), key);
+                buf.writeln(&"}");
+                hint = hint.max(self.pop_hint());
+                self.add_hint(hint);
+
+                buf.writeln(&"}");
+            }
+            None => self.write_message(buf, catalogs.default(), key),
+        }
+        buf.writeln(&"}");
+    }
+
+    // Message of a catalog, its plural form is chosen by the `count` argument
+    fn write_message(&mut self, buf: &mut String, catalog: &Catalog, key: &str) {
+        let forms = &catalog.messages[key];
+        if forms.len() == 1 {
+            return self.write_parts(buf, &forms[0]);
+        }
+
+        buf.writeln(&"let _n = ::wearte::PluralCount::plural_count(count);");
+        buf.writeln(&"#[allow(unused_parens)]");
+        writeln!(buf, "let _form = {};", catalog.plural).unwrap();
+        buf.writeln(&"match _form {");
+        let mut hint = 0;
+        for (i, form) in forms.iter().enumerate() {
+            self.hints.push(0);
+            if i + 1 == forms.len() {
+                buf.writeln(&"_ => {");
+            } else {
+                writeln!(buf, "{} => {{", i).unwrap();
+            }
+            self.write_parts(buf, form);
+            buf.writeln(&"}");
+            hint = hint.max(self.pop_hint());
+        }
+        self.add_hint(hint);
+        buf.writeln(&"}");
+    }
+
+    // Literals as the template ones, arguments as escaped expressions
+    fn write_parts(&mut self, buf: &mut String, msg: &str) {
+        for part in parts(msg) {
+            match part {
+                Part::Lit(lit) => self.write_lit(buf, &lit),
+                Part::Arg(arg) => self.write_expr(buf, arg, false),
+            }
+        }
+    }
+
+    // Index in `locales` of the `locale_field` of the template, also inside of
+    // loops and partials
+    fn locale_expr(&mut self, locale: &'a syn::Expr, locales: &[&str]) -> String {
+        let on = mem::take(&mut self.on);
+        let scp = mem::replace(&mut self.scp, vec![vec![self.root.to_owned()]]);
+        self.visit_expr(locale);
+        self.on = on;
+        self.scp = scp;
+
+        format!(
+            "::wearte::negotiate(::std::convert::AsRef::<str>::as_ref(&({})), &{:?})",
+            mem::take(&mut self.buf_t),
+            locales
+        )
+    }
+
+    fn visit_asset(&mut self, buf: &mut String, ws: &Ws, kind: Asset, path: &str) {
+        let c = self
+            .c
+            .assets
+            .as_ref()
+            .unwrap_or_else(|| panic!("assets need the `[assets]` table in wearte.toml"));
+        let lit = match kind {
+            Asset::Url => assets::url(c, path),
+            Asset::Integrity => assets::integrity(c, path),
+        };
+
+        self.handle_ws(ws);
+        self.write_buf_writable(buf);
+        self.write_lit(buf, &lit);
+    }
+
+    // File of the template directories as a literal, escaped at runtime by the
+    // escaper of the template or encoded in base64 as is
+    fn visit_embed(&mut self, buf: &mut String, ws: &Ws, path: &str, encoding: Encoding) {
+        let file = self.c.find_file(path).unwrap_or_else(|| {
+            panic!(
+                "raw partial {:?} not found in {:?}",
+                path,
+                self.c.get_dirs()
+            )
+        });
+        let bytes =
+            fs::read(&file).unwrap_or_else(|_| panic!("unable to read raw partial {:?}", file));
+        // trailing whitespace as in the partials
+        let trim = self.s.trim_trailing;
+        let text = || {
+            let text = str::from_utf8(&bytes).unwrap_or_else(|_| {
+                panic!("raw partial {:?} isn't UTF-8, embed it with `base64`", file)
+            });
+            if trim {
+                text.trim_end()
+            } else {
+                text
+            }
+        };
+
+        self.handle_ws(ws);
+        self.write_buf_writable(buf);
+        match encoding {
+            Encoding::Verbatim => self.write_lit(buf, text()),
+            Encoding::Escape => {
+                let lit = text();
+                self.write_expr(buf, &format!("{:#?}", lit), false);
+                self.add_hint(lit.len().saturating_sub(EXPR_HINT));
+            }
+            Encoding::Base64 => {
+                let lit = STANDARD.encode(&bytes);
+                self.write_lit(buf, &lit);
+            }
+        }
+    }
+
+    fn catalogs(&mut self) -> Rc<Catalogs> {
+        let c = self.c;
+        self.catalogs
+            .get_or_insert_with(|| match c.i18n {
+                Some(ref i18n) => Rc::new(i18n::load(i18n)),
+                None => panic!("translations need the `[i18n]` table in wearte.toml"),
+            })
+            .clone()
+    }
+
+    // Definition in the current template, or in the one imported as `alias`
+    fn find_macro(&self, alias: Option<&str>, name: &str) -> MacroDef<'a> {
+        let ctx: Context<'a> = self.ctx;
+        let path = match alias {
+            Some(alias) => {
+                let import = ctx
+                    .get(&self.on_path)
+                    .unwrap()
+                    .iter()
+                    .find_map(|n| match n {
+                        Node::Import(_, path, a) if *a == alias => Some(*path),
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "`{}` is not imported in template {}",
+                            alias,
+                            self.on_path.display()
+                        )
+                    });
+                self.c.resolve_partial(&self.on_path, import)
+            }
+            None => self.on_path.clone(),
+        };
+
+        let def = ctx.get(&path).unwrap().iter().find_map(|n| match n {
+            Node::Macro(ws, n, params, nodes) if *n == name => Some((ws, params, &nodes[..])),
+            _ => None,
+        });
+        match def {
+            Some((ws, params, nodes)) => (path, ws, params, nodes),
+            None => panic!("macro `{}` not found in template {}", name, path.display()),
+        }
+    }
+
     fn write_buf_writable(&mut self, buf: &mut String) {
         if self.buf_w.is_empty() {
             return;
@@ -483,7 +1265,7 @@ impl<'a> Generator<'a> {
                     buf_lit.write_str(s).unwrap();
                 };
             }
-            writeln!(buf, "_fmt.write_str({:#?})?;", &buf_lit).unwrap();
+            self.write_lit(buf, &buf_lit);
             return;
         }
 
@@ -492,31 +1274,204 @@ impl<'a> Generator<'a> {
                 Writable::Lit(s) => buf_lit.write_str(s).unwrap(),
                 Writable::Expr(s, wrapped) => {
                     if !buf_lit.is_empty() {
-                        writeln!(
-                            buf,
-                            "_fmt.write_str({:#?})?;",
-                            &mem::replace(&mut buf_lit, String::new())
-                        )
-                        .unwrap();
-                    }
-
-                    buf.push('(');
-                    if wrapped || self.s.wrapped {
-                        buf.write(&s);
-                    } else {
-                        // wrap
-                        write!(buf, "::wearte::MarkupAsStr::from(&{})", s).unwrap();
+                        self.write_lit(buf, &mem::replace(&mut buf_lit, String::new()));
                     }
-                    buf.writeln(&").fmt(_fmt)?;");
+                    self.write_expr(buf, &s, wrapped);
                 }
             }
         }
 
         if !buf_lit.is_empty() {
-            writeln!(buf, "_fmt.write_str({:#?})?;", buf_lit).unwrap();
+            self.write_lit(buf, &buf_lit);
+        }
+    }
+
+    // A field of the context may be a nested template, its size hint is added
+    // when the type implements `Template` and its MIME type must be accepted by
+    // the escaper when the expression is escaped
+    fn child_hint(&mut self, expr: &syn::Expr, escaped: bool) {
+        if self.bytes {
+            return;
+        }
+
+        let ident = match expr {
+            syn::Expr::Path(syn::ExprPath {
+                path, qself: None, ..
+            }) if path.segments.len() == 1 => path.segments[0].ident.to_string(),
+            syn::Expr::Field(syn::ExprField {
+                base,
+                member: syn::Member::Named(m),
+                ..
+            }) => match &**base {
+                syn::Expr::Path(syn::ExprPath {
+                    path, qself: None, ..
+                }) if path.segments.len() == 1 && path.segments[0].ident == "self" => m.to_string(),
+                _ => return,
+            },
+            _ => return,
+        };
+
+        // resolved to the field, not to a local or a loop item
+        let field = if self.fields.contains(&ident) {
+            format!("(*{})", ident)
+        } else {
+            format!("{}.{}", self.root, ident)
+        };
+        if self.buf_t != field {
+            return;
+        }
+
+        if let Some((_, ty)) = self.s.types.iter().find(|(f, _)| *f == ident) {
+            if escaped && !self.s.wrapped {
+                let msg = format!(
+                    "the MIME type of the nested template `{}` isn't accepted by the escaper of {}",
+                    ident,
+                    self.s.path.display()
+                );
+                self.mime_checks.push(format!(
+                    "const {{ assert!(::wearte::child_accepts(\
+                     <{} as ::wearte::escapers::Escaper>::MIMES, \
+                     ::wearte::Child::<{}>::MIME), {:?}) }};",
+                    self.s.escaper,
+                    quote!(#ty),
+                    msg
+                ));
+            }
+            let loops = self.on.iter().filter(|o| matches!(o, On::Each(_))).count();
+            let hint = format!(
+                "(&::wearte::Child::<{}>::new()).child_size_hint()",
+                quote!(#ty)
+            );
+            self.children.push(match loops {
+                0 => hint,
+                n => format!("{} * {}", LOOP_HINT.pow(n as u32), hint),
+            });
         }
     }
 
+    // Size hint of the template, plus the size hints of the nested templates
+    fn size_hint_expr(&self) -> String {
+        if self.children.is_empty() {
+            return self.size_hint.to_string();
+        }
+
+        let mut hint = String::new();
+        hint.writeln(&"{");
+        hint.writeln(&"#[allow(unused_imports)]");
+        hint.writeln(&"use ::wearte::{ChildOther as _, ChildTemplate as _};");
+        write!(hint, "{}", self.size_hint).unwrap();
+        for child in &self.children {
+            write!(hint, " + {}", child).unwrap();
+        }
+        hint.writeln(&"\n}");
+        hint
+    }
+
+    fn write_expr(&mut self, buf: &mut String, s: &str, wrapped: bool) {
+        self.add_hint(EXPR_HINT);
+
+        if self.bytes {
+            if wrapped || self.s.wrapped {
+                writeln!(buf, "(&::wearte::Wrap(&({}))).render_raw_bytes(_buf)?;", s).unwrap();
+            } else {
+                writeln!(
+                    buf,
+                    "(&::wearte::Wrap(&({}))).render_escaped_bytes::<{}, _>(_buf)?;",
+                    s, self.s.escaper
+                )
+                .unwrap();
+            }
+        } else if wrapped || self.s.wrapped {
+            writeln!(buf, "({}).fmt(_fmt)?;", s).unwrap();
+        } else {
+            // wrap
+            writeln!(
+                buf,
+                "(&::wearte::Wrap(&({}))).render_escaped::<{}, _>(_fmt)?;",
+                s, self.s.escaper
+            )
+            .unwrap();
+        }
+    }
+
+    fn write_lit(&mut self, buf: &mut String, lit: &str) {
+        let lit = match self.minifier.as_mut() {
+            Some(m) => m.minify(lit),
+            None => lit.to_owned(),
+        };
+
+        self.add_hint(lit.len());
+        if lit.is_empty() {
+        } else if self.bytes {
+            writeln!(buf, "_buf.extend_from_slice({:#?}.as_bytes());", lit).unwrap();
+        } else {
+            writeln!(buf, "_fmt.write_str({:#?})?;", lit).unwrap();
+        }
+    }
+
+    // Field of the template context
+    pub(self) fn write_field(&mut self, ident: &str) {
+        if self.scp[0][0] == self.root {
+            self.check_field(ident);
+            if self.fields.iter().any(|f| f == ident) {
+                return write!(self.buf_t, "(*{})", ident).unwrap();
+            }
+        }
+
+        write!(self.buf_t, "{}.{}", self.scp[0][0], ident).unwrap();
+    }
+
+    // Only the named fields are known, other identifiers can come through `Deref`
+    // or from other macros, so they are an error only with `check_fields`
+    fn check_field(&mut self, ident: &str) {
+        if let Some(ref fields) = self.s.fields {
+            if self.s.check_fields {
+                let src = self.sources.get(&self.on_path).map(String::as_str);
+                validator::field(ident, fields, &self.on_path, src);
+            }
+            self.used.insert(ident.to_owned());
+        }
+    }
+
+    // The whole context is used, e.g. `{{ self }}` or in a macro
+    pub(self) fn use_context(&mut self) {
+        if self.scp[0][0] == self.root {
+            if let Some(ref fields) = self.s.fields {
+                self.used.extend(fields.iter().cloned());
+            }
+        }
+    }
+
+    // A compiler warning for each field never read, the use of a deprecated constant
+    fn warn_unused(&self, buf: &mut String) {
+        if let Some(ref fields) = self.s.fields {
+            for field in fields.iter().filter(|f| !self.used.contains(*f)) {
+                let note = format!(
+                    "field `{}` is never read by template {}",
+                    field,
+                    self.s.path.display()
+                );
+                buf.writeln(&"{");
+                writeln!(buf, "#[deprecated(note = {:?})]", note).unwrap();
+                buf.writeln(&"#[allow(non_upper_case_globals)]");
+                writeln!(buf, "const __wearte_unused_{}: () = ();", field).unwrap();
+                buf.writeln(&"#[allow(clippy::let_unit_value)]");
+                writeln!(buf, "let _ = __wearte_unused_{};", field).unwrap();
+                buf.writeln(&"}");
+            }
+        }
+    }
+
+    /* Helper methods for the output size estimate */
+    fn add_hint(&mut self, n: usize) {
+        let last = self.hints.last_mut().unwrap();
+        *last = last.saturating_add(n);
+    }
+
+    fn pop_hint(&mut self) -> usize {
+        self.hints.pop().unwrap()
+    }
+
     /* Helper methods for dealing with whitespace nodes */
     fn skip_ws(&mut self) {
         self.next_ws = None;
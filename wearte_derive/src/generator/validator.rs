@@ -1,3 +1,7 @@
+use std::{cmp, fmt::Write, path::Path};
+
+use crate::i18n::{parts, Catalogs, Part};
+
 pub(super) fn expression(e: &syn::Expr) {
     use syn::Expr::*;
     match e {
@@ -77,3 +81,250 @@ pub(super) fn scope(e: &syn::Expr) {
         ),
     }
 }
+
+// Identifier of the template context that isn't a field of the struct
+pub(super) fn field(ident: &str, fields: &[String], path: &Path, src: Option<&str>) {
+    if fields.iter().any(|f| f == ident) {
+        return;
+    }
+
+    let mut msg = format!("unknown identifier `{}` in template ", ident);
+    match src.and_then(|src| locate(src, ident)) {
+        Some((line, col)) => write!(msg, "{}:{}:{}", path.display(), line, col).unwrap(),
+        None => write!(msg, "{}", path.display()).unwrap(),
+    }
+    match suggest(ident, fields) {
+        Some(field) => write!(msg, "\n  did you mean `{}`?", field).unwrap(),
+        None if fields.is_empty() => msg.push_str("\n  the template context has no fields"),
+        None => write!(msg, "\n  available fields: {}", fields.join(", ")).unwrap(),
+    }
+
+    panic!("{}", msg)
+}
+
+// Translated message, the key must be in the default catalog and the arguments
+// must be the ones of the message, `count` chooses the plural form
+pub(super) fn message(key: &str, args: &[&str], catalogs: &Catalogs, path: &Path) {
+    let default = catalogs.default();
+    let forms = match default.messages.get(key) {
+        Some(forms) => forms,
+        None => {
+            let keys: Vec<String> = default.messages.keys().cloned().collect();
+            let mut msg = format!(
+                "unknown message `{}` in template {}, it isn't in the catalog of `{}`",
+                key,
+                path.display(),
+                default.locale
+            );
+            if let Some(k) = suggest(key, &keys) {
+                write!(msg, "\n  did you mean `{}`?", k).unwrap();
+            }
+            panic!("{}", msg)
+        }
+    };
+
+    for (i, arg) in args.iter().enumerate() {
+        if args[..i].contains(arg) {
+            panic!("argument `{}` of message `{}` is given twice", arg, key);
+        }
+    }
+
+    let plural = catalogs
+        .catalogs
+        .iter()
+        .any(|c| c.messages.get(key).is_some_and(|forms| 1 < forms.len()));
+    if plural && !args.contains(&"count") {
+        panic!(
+            "message `{}` has plural forms, give the number as argument `count` in template {}",
+            key,
+            path.display()
+        );
+    }
+
+    // a message without translation falls back to the default locale
+    for catalog in &catalogs.catalogs {
+        if let Some(forms) = catalog.messages.get(key) {
+            for part in forms.iter().flat_map(|f| parts(f)) {
+                if let Part::Arg(arg) = part {
+                    if !args.contains(&arg) {
+                        panic!(
+                            "message `{}` of `{}` uses argument `{}`, not given in template {}",
+                            key,
+                            catalog.locale,
+                            arg,
+                            path.display()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    let used: Vec<Part> = forms.iter().flat_map(|f| parts(f)).collect();
+    for arg in args {
+        if !(used.contains(&Part::Arg(arg)) || plural && *arg == "count") {
+            panic!(
+                "argument `{}` isn't used by message `{}` of `{}` in template {}",
+                arg,
+                key,
+                default.locale,
+                path.display()
+            );
+        }
+    }
+}
+
+// Mime type of the template, it goes as is into a `Content-Type` header, so a bad
+// one is an error here and not a panic of the response
+pub(super) fn mime(mime: &str, path: &Path) {
+    let is_token = |s: &str| {
+        !s.is_empty()
+            && s.bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+    };
+
+    let mut parts = mime.split(';');
+    let essence = parts.next().unwrap().trim();
+    let valid = essence
+        .split_once('/')
+        .is_some_and(|(ty, sub)| is_token(ty) && is_token(sub))
+        && parts.all(|p| {
+            p.trim().split_once('=').is_some_and(|(name, value)| {
+                is_token(name.trim())
+                    && !value.is_empty()
+                    && value.bytes().all(|b| b.is_ascii_graphic() || b == b' ')
+            })
+        });
+
+    if !valid {
+        panic!(
+            "invalid mime type {:?} of template {}, set a `type/subtype` with the `mime` \
+             and `charset` attributes or in the `[mime]` table of the config",
+            mime,
+            path.display()
+        );
+    }
+}
+
+// Closest field by edit distance, a third of the length at most
+fn suggest<'a>(ident: &str, fields: &'a [String]) -> Option<&'a str> {
+    let max = cmp::max(1, ident.chars().count() / 3);
+    fields
+        .iter()
+        .map(|f| (distance(ident, f), f))
+        .filter(|(d, _)| *d <= max)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, f)| f.as_str())
+}
+
+// Levenshtein distance counting the transposition of two characters as one edit
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = cmp::min(
+                cmp::min(d[i - 1][j] + 1, d[i][j - 1] + 1),
+                d[i - 1][j - 1] + cost,
+            );
+            if 1 < i && 1 < j && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = cmp::min(d[i][j], d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+// Line and column of the first use of `ident` inside a tag, 1-based
+fn locate(src: &str, ident: &str) -> Option<(usize, usize)> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut start = 0;
+    while let Some(open) = src[start..].find("{{").map(|i| start + i) {
+        let close = src[open..].find("}}").map_or(src.len(), |i| open + i);
+        let tag = &src[open..close];
+        let mut from = 0;
+        while let Some(i) = tag[from..].find(ident).map(|i| from + i) {
+            let before = tag[..i].chars().next_back();
+            let after = tag[i + ident.len()..].chars().next();
+            if !before.is_some_and(|c| is_ident(c) || c == '.') && !after.is_some_and(is_ident) {
+                let pos = open + i;
+                let line = src[..pos].matches('\n').count() + 1;
+                let col = src[..pos].rsplit('\n').next().unwrap().chars().count() + 1;
+                return Some((line, col));
+            }
+            from = i + ident.len();
+        }
+        start = close;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_suggest() {
+        let fields = vec!["title".to_owned(), "items".to_owned(), "id".to_owned()];
+        assert_eq!(suggest("titel", &fields), Some("title"));
+        assert_eq!(suggest("item", &fields), Some("items"));
+        assert_eq!(suggest("ids", &fields), Some("id"));
+        assert_eq!(suggest("name", &fields), None);
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_locate() {
+        let src = "<h1>{{ title }}</h1>\n<p>{{ a.titel }} titel {{#if titel}}";
+        assert_eq!(locate(src, "titel"), Some((2, 30)));
+        assert_eq!(locate(src, "tit"), None);
+        assert_eq!(locate("{{ é }}{{ x }}", "x"), Some((1, 11)));
+    }
+
+    #[test]
+    fn test_mime() {
+        for m in &[
+            "text/html",
+            "text/html; charset=utf-8",
+            "application/vnd.api+json",
+            "text/plain;charset=\"utf-8\"",
+        ] {
+            mime(m, Path::new("a.html"));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid mime type \"text/html; charset=ü\" of template a.html")]
+    fn test_mime_invalid() {
+        mime("text/html; charset=ü", Path::new("a.html"));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid mime type \"html\"")]
+    fn test_mime_essence() {
+        mime("html", Path::new("a.html"));
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown identifier `titel` in template a.html:1:4\n  did you mean")]
+    fn test_field() {
+        field(
+            "titel",
+            &["title".to_owned()],
+            Path::new("a.html"),
+            Some("{{ titel }}"),
+        );
+    }
+}
@@ -3,12 +3,188 @@ use syn::visit::Visit;
 
 use std::path::PathBuf;
 
-use wearte_config::Config;
+use wearte_config::{Config, Whitespace, LOCALE};
 
 use crate::generator::EWrite;
 
 pub(crate) fn visit_derive<'a>(i: &'a syn::DeriveInput, config: &'a Config) -> Struct<'a> {
-    StructBuilder::default().build(i, config)
+    let mut s = StructBuilder::default().build(
+        &i.attrs,
+        i.ident.to_string(),
+        &i.ident,
+        &i.generics,
+        config,
+    );
+    if let syn::Data::Struct(ref data) = i.data {
+        s.fields = field_names(&data.fields);
+        s.types = field_types(&data.fields);
+    }
+
+    s
+}
+
+// A template per locale when the path has `{locale}`, dispatched on `locale_field`
+pub(crate) fn visit_locales<'a>(
+    i: &'a syn::DeriveInput,
+    config: &'a Config,
+) -> Option<Locales<'a>> {
+    let mut b = StructBuilder::default();
+    for it in &i.attrs {
+        b.visit_attribute(it);
+    }
+    let path = b.path.filter(|p| p.contains(LOCALE))?;
+    if b.locale_field.is_none() {
+        panic!(
+            "template {} with {} in the path needs the locale_field attribute",
+            i.ident, LOCALE
+        );
+    }
+    if b.size_hint.is_some_and(|h| h == "adaptive") {
+        panic!(
+            "size_hint = \"adaptive\" is not supported with {} in the path",
+            LOCALE
+        );
+    }
+
+    let locales = config.get_locales(&path);
+    let fallback = match config.i18n {
+        Some(ref i18n) => i18n.default,
+        None => panic!(
+            "template {} with {} in the path needs a fallback, the default locale of the \
+             `[i18n]` table in wearte.toml",
+            i.ident, LOCALE
+        ),
+    };
+    let fallback = locales
+        .iter()
+        .position(|l| l == fallback)
+        .unwrap_or_else(|| {
+            panic!(
+                "template {} of the fallback locale `{}` not found",
+                path, fallback
+            )
+        });
+
+    let templates = locales
+        .into_iter()
+        .map(|locale| {
+            let b = StructBuilder {
+                variant_locale: Some(locale.clone()),
+                ..StructBuilder::default()
+            };
+            let mut s = b.build(&i.attrs, i.ident.to_string(), &i.ident, &i.generics, config);
+            if let syn::Data::Struct(ref data) = i.data {
+                s.fields = field_names(&data.fields);
+                s.types = field_types(&data.fields);
+            }
+            (locale, s)
+        })
+        .collect();
+
+    Some(Locales {
+        templates,
+        fallback,
+    })
+}
+
+// Each variant has its own template, or renders its only field
+pub(crate) fn visit_enum<'a>(
+    i: &'a syn::DeriveInput,
+    data: &'a syn::DataEnum,
+    config: &'a Config,
+) -> Enum<'a> {
+    if i.attrs.iter().any(is_template) {
+        panic!(
+            "template attributes of enum {} must be on its variants",
+            i.ident
+        );
+    }
+
+    let variants = data
+        .variants
+        .iter()
+        .map(|v| {
+            let attrs: Vec<syn::Attribute> =
+                v.attrs.iter().filter(|a| is_template(a)).cloned().collect();
+            let template = if attrs.is_empty() {
+                match v.fields {
+                    syn::Fields::Unnamed(ref f) if f.unnamed.len() == 1 => None,
+                    _ => panic!(
+                        "variant {} must have a template attribute, or a single field implementing \
+                         Template",
+                        v.ident
+                    ),
+                }
+            } else {
+                let mut s = StructBuilder::default().build(
+                    &attrs,
+                    format!("{}{}", i.ident, v.ident),
+                    &i.ident,
+                    &i.generics,
+                    config,
+                );
+                if let syn::Fields::Unnamed(ref f) = v.fields {
+                    if f.unnamed.len() != 1 {
+                        panic!(
+                            "tuple variant {} with a template must have one field",
+                            v.ident
+                        );
+                    }
+                }
+                if s.adaptive {
+                    panic!(
+                        "size_hint = \"adaptive\" is not supported on variant {}",
+                        v.ident
+                    );
+                }
+                s.fields = field_names(&v.fields);
+                s.types = field_types(&v.fields);
+                Some(s)
+            };
+
+            Variant {
+                ident: &v.ident,
+                fields: &v.fields,
+                template,
+            }
+        })
+        .collect();
+
+    Enum {
+        variants,
+        ident: &i.ident,
+        generics: &i.generics,
+    }
+}
+
+// Names of the fields in scope of the template, tuple fields aren't checked
+fn field_names(fields: &syn::Fields) -> Option<Vec<String>> {
+    match fields {
+        syn::Fields::Named(f) => Some(
+            f.named
+                .iter()
+                .map(|f| f.ident.as_ref().unwrap().to_string())
+                .collect(),
+        ),
+        syn::Fields::Unit => Some(vec![]),
+        syn::Fields::Unnamed(_) => None,
+    }
+}
+
+// Types of the named fields, for the size hint of nested templates
+fn field_types(fields: &syn::Fields) -> Vec<(String, &syn::Type)> {
+    match fields {
+        syn::Fields::Named(f) => f
+            .named
+            .iter()
+            .map(|f| (f.ident.as_ref().unwrap().to_string(), &f.ty))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn is_template(a: &syn::Attribute) -> bool {
+    a.path.segments.len() == 1 && a.path.segments[0].ident == "template"
 }
 
 #[derive(Debug)]
@@ -17,85 +193,208 @@ pub(crate) struct Struct<'a> {
     pub path: PathBuf,
     pub print: Print,
     pub wrapped: bool,
+    pub escaper: String,
+    pub whitespace: Whitespace,
+    pub trim_trailing: bool,
+    pub minify: bool,
+    pub adaptive: bool,
+    pub mime: Option<String>,
+    pub charset: Option<String>,
+    pub fields: Option<Vec<String>>,
+    pub types: Vec<(String, &'a syn::Type)>,
+    pub check_fields: bool,
+    pub warn_unused: bool,
+    // field with the locale of the translations
+    pub locale: Option<syn::Expr>,
     ident: &'a syn::Ident,
     generics: &'a syn::Generics,
 }
 
 impl<'a> Struct<'a> {
-    pub fn implement_head(&self, t: &str, buf: &mut EWrite) {
-        let (impl_generics, orig_ty_generics, where_clause) = self.generics.split_for_impl();
+    pub fn implement_head(&self, t: &str, buf: &mut dyn EWrite) {
+        implement_head(t, self.ident, self.generics, buf)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Enum<'a> {
+    pub variants: Vec<Variant<'a>>,
+    pub ident: &'a syn::Ident,
+    generics: &'a syn::Generics,
+}
 
-        writeln!(
-            buf,
-            "{} {} for {}{} {{",
-            quote!(impl#impl_generics),
-            t,
-            self.ident,
-            quote!(#orig_ty_generics #where_clause)
-        )
-        .unwrap()
+impl<'a> Enum<'a> {
+    pub fn implement_head(&self, t: &str, buf: &mut dyn EWrite) {
+        implement_head(t, self.ident, self.generics, buf)
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct Locales<'a> {
+    pub templates: Vec<(String, Struct<'a>)>,
+    // index of the template used when the locale doesn't match any other
+    pub fallback: usize,
+}
+
+#[derive(Debug)]
+pub(crate) struct Variant<'a> {
+    pub ident: &'a syn::Ident,
+    pub fields: &'a syn::Fields,
+    // `None` renders the only field
+    pub template: Option<Struct<'a>>,
+}
+
+fn implement_head(t: &str, ident: &syn::Ident, generics: &syn::Generics, buf: &mut dyn EWrite) {
+    let (impl_generics, orig_ty_generics, where_clause) = generics.split_for_impl();
+
+    writeln!(
+        buf,
+        "{} {} for {}{} {{",
+        quote!(impl#impl_generics),
+        t,
+        ident,
+        quote!(#orig_ty_generics #where_clause)
+    )
+    .unwrap()
+}
+
 struct StructBuilder {
     assured: Option<bool>,
+    charset: Option<String>,
+    escape: Option<String>,
     ext: Option<String>,
+    locale_field: Option<String>,
+    mime: Option<String>,
+    minify: Option<bool>,
     path: Option<String>,
     print: Option<String>,
+    size_hint: Option<String>,
     src: Option<String>,
+    trim_trailing: Option<bool>,
+    // locale replacing `{locale}` in the path
+    variant_locale: Option<String>,
+    warn_unused: Option<bool>,
+    whitespace: Option<String>,
+    check_fields: Option<bool>,
 }
 
 impl Default for StructBuilder {
     fn default() -> Self {
         StructBuilder {
             assured: None,
+            charset: None,
+            escape: None,
             ext: None,
+            locale_field: None,
+            mime: None,
+            minify: None,
             path: None,
             print: None,
+            size_hint: None,
             src: None,
+            trim_trailing: None,
+            variant_locale: None,
+            warn_unused: None,
+            whitespace: None,
+            check_fields: None,
         }
     }
 }
 
 impl StructBuilder {
+    // `name` is the file name of inline sources
     fn build<'n>(
         mut self,
-        syn::DeriveInput {
-            attrs,
-            ident,
-            generics,
-            ..
-        }: &'n syn::DeriveInput,
+        attrs: &[syn::Attribute],
+        name: String,
+        ident: &'n syn::Ident,
+        generics: &'n syn::Generics,
         config: &'n Config,
     ) -> Struct<'n> {
         for it in attrs {
             self.visit_attribute(it)
         }
+        if let Some(path) = self.path.as_mut().filter(|p| p.contains(LOCALE)) {
+            match self.variant_locale {
+                Some(ref locale) => *path = path.replace(LOCALE, locale),
+                None => panic!("{} in the path is only supported on structs", LOCALE),
+            }
+        }
 
+        let trim_trailing = self.trim_trailing.unwrap_or(config.trim_trailing);
         let (path, src) = match (self.src, self.ext) {
             (Some(src), ext) => (
-                PathBuf::from(quote!(#ident).to_string())
-                    .with_extension(ext.unwrap_or(DEFAULT_EXTENSION.to_owned())),
+                PathBuf::from(name).with_extension(ext.unwrap_or(DEFAULT_EXTENSION.to_owned())),
                 src,
             ),
-            (None, None) => config.get_template(&self.path.expect("some valid path")),
+            (None, None) => {
+                config.get_template(&self.path.expect("some valid path"), trim_trailing)
+            }
             (None, Some(_)) => panic!("'ext' attribute cannot be used with 'path' attribute"),
         };
 
-        let wrapped = self.assured.unwrap_or_else(|| {
-            if let Some(e) = path.extension() {
-                if HTML_EXTENSIONS.contains(&e.to_str().unwrap()) {
-                    return false;
+        let escaper = match (self.assured, self.escape) {
+            (Some(true), Some(_)) => {
+                panic!("'assured' attribute cannot be used with 'escape' attribute")
+            }
+            (Some(true), None) => None,
+            (_, Some(escape)) => resolve_escaper(&escape),
+            (assured, None) => {
+                let ext = path.extension().map(|e| e.to_str().unwrap());
+                // served as HTML by the `mime` attribute or the `[mime]` table
+                let html_mime = self
+                    .mime
+                    .as_deref()
+                    .or_else(|| ext.and_then(|e| config.get_mime(e)))
+                    .is_some_and(|m| m.split(';').next().unwrap().trim() == "text/html");
+                match (ext.and_then(|e| config.get_escaper(e)), ext) {
+                    (Some(escape), _) => resolve_escaper(escape),
+                    (None, Some(e)) if HTML_EXTENSIONS.contains(&e) => {
+                        Some(HTML_ESCAPER.to_owned())
+                    }
+                    (None, _) if html_mime => Some(HTML_ESCAPER.to_owned()),
+                    // assured false
+                    (None, _) if assured.is_some() => Some(HTML_ESCAPER.to_owned()),
+                    (None, _) => None,
                 }
             }
+        };
 
-            true
+        let minify = self.minify.unwrap_or_else(|| {
+            config.minify
+                && match path.extension().and_then(|e| e.to_str()) {
+                    Some(ext) => ext == DEFAULT_EXTENSION || ext == "htm",
+                    None => false,
+                }
         });
+
+        let adaptive = match self.size_hint.as_deref() {
+            Some("adaptive") => true,
+            Some("static") | None => false,
+            Some(v) => panic!("invalid value for size_hint attribute: {}", v),
+        };
+
         Struct {
             src,
             path,
             print: self.print.into(),
-            wrapped,
+            wrapped: escaper.is_none(),
+            escaper: escaper.unwrap_or_default(),
+            whitespace: self
+                .whitespace
+                .map_or(config.whitespace, |w| Whitespace::from(Some(w.as_ref()))),
+            trim_trailing,
+            minify,
+            adaptive,
+            mime: self.mime,
+            charset: self.charset,
+            fields: None,
+            types: vec![],
+            check_fields: self.check_fields.unwrap_or(config.check_fields),
+            warn_unused: self.warn_unused.unwrap_or(config.warn_unused),
+            locale: self.locale_field.map(|f| {
+                syn::parse_str(&f).unwrap_or_else(|_| panic!("invalid locale_field: {}", f))
+            }),
             generics,
             ident,
         }
@@ -158,6 +457,76 @@ impl<'a> Visit<'a> for StructBuilder {
                     panic!("attribute assured must be boolean literal");
                 }
             }
+            "escape" => {
+                if let syn::Lit::Str(ref s) = lit {
+                    self.escape = Some(s.value());
+                } else {
+                    panic!("attribute escape must be string literal");
+                }
+            }
+            "whitespace" => {
+                if let syn::Lit::Str(ref s) = lit {
+                    self.whitespace = Some(s.value());
+                } else {
+                    panic!("attribute whitespace must be string literal");
+                }
+            }
+            "trim_trailing" => {
+                if let syn::Lit::Bool(ref s) = lit {
+                    self.trim_trailing = Some(s.value);
+                } else {
+                    panic!("attribute trim_trailing must be boolean literal");
+                }
+            }
+            "minify" => {
+                if let syn::Lit::Bool(ref s) = lit {
+                    self.minify = Some(s.value);
+                } else {
+                    panic!("attribute minify must be boolean literal");
+                }
+            }
+            "check_fields" => {
+                if let syn::Lit::Bool(ref s) = lit {
+                    self.check_fields = Some(s.value);
+                } else {
+                    panic!("attribute check_fields must be boolean literal");
+                }
+            }
+            "warn_unused" => {
+                if let syn::Lit::Bool(ref s) = lit {
+                    self.warn_unused = Some(s.value);
+                } else {
+                    panic!("attribute warn_unused must be boolean literal");
+                }
+            }
+            "locale_field" => {
+                if let syn::Lit::Str(ref s) = lit {
+                    self.locale_field = Some(s.value());
+                } else {
+                    panic!("attribute locale_field must be string literal");
+                }
+            }
+            "size_hint" => {
+                if let syn::Lit::Str(ref s) = lit {
+                    self.size_hint = Some(s.value());
+                } else {
+                    panic!("attribute size_hint must be string literal");
+                }
+            }
+            "mime" => {
+                if let syn::Lit::Str(ref s) = lit {
+                    self.mime = Some(s.value());
+                } else {
+                    panic!("attribute mime must be string literal");
+                }
+            }
+            "charset" => {
+                if let syn::Lit::Str(ref s) = lit {
+                    self.charset = Some(s.value());
+                } else {
+                    panic!("attribute charset must be string literal");
+                }
+            }
             "ext" => {
                 if let syn::Lit::Str(ref s) = lit {
                     self.ext = Some(s.value());
@@ -192,6 +561,26 @@ impl From<Option<String>> for Print {
     }
 }
 
+// Built-in escaper names or path to a type implementing `wearte::escapers::Escaper`
+fn resolve_escaper(escape: &str) -> Option<String> {
+    match escape {
+        "none" => None,
+        "html" => Some(HTML_ESCAPER.to_owned()),
+        "json" => Some("::wearte::escapers::Json".to_owned()),
+        "xml" => Some("::wearte::escapers::Xml".to_owned()),
+        path => {
+            if syn::parse_str::<syn::Path>(path).is_err() {
+                panic!(
+                    "invalid escaper '{}', must be a built-in escaper or a type path",
+                    path
+                );
+            }
+            Some(path.to_owned())
+        }
+    }
+}
+
+pub(crate) static HTML_ESCAPER: &str = "::wearte::escapers::Html";
 static DEFAULT_EXTENSION: &str = "html";
 static HTML_EXTENSIONS: [&str; 6] = [
     DEFAULT_EXTENSION,
@@ -221,6 +610,19 @@ mod test {
         let _ = visit_derive(&i, &config);
     }
 
+    #[test]
+    #[should_panic(expected = "locale_field")]
+    fn test_locales_panic() {
+        let src = r#"
+            #[derive(Template)]
+            #[template(path = "{locale}/terms.html")]
+            struct Test;
+        "#;
+        let i = parse_str::<syn::DeriveInput>(src).unwrap();
+        let config = Config::new("");
+        let _ = visit_locales(&i, &config);
+    }
+
     #[test]
     fn test() {
         let src = r#"
@@ -236,4 +638,92 @@ mod test {
         assert_eq!(s.print, Print::Code);
         assert_eq!(s.wrapped, true);
     }
+
+    #[test]
+    fn test_escaper() {
+        let src = r#"
+            #[derive(Template)]
+            #[template(src = "", ext = "json")]
+            struct Test;
+        "#;
+        let i = parse_str::<syn::DeriveInput>(src).unwrap();
+        let config = Config::new("[escapers]\njson = \"json\"");
+        let s = visit_derive(&i, &config);
+        assert_eq!(s.wrapped, false);
+        assert_eq!(s.escaper, "::wearte::escapers::Json");
+
+        let src = r#"
+            #[derive(Template)]
+            #[template(src = "", ext = "html", escape = "crate::Latex")]
+            struct Test;
+        "#;
+        let i = parse_str::<syn::DeriveInput>(src).unwrap();
+        let s = visit_derive(&i, &config);
+        assert_eq!(s.wrapped, false);
+        assert_eq!(s.escaper, "crate::Latex");
+
+        let config = Config::new("[escapers]\nhtml = \"none\"");
+        let src = r#"
+            #[derive(Template)]
+            #[template(src = "", ext = "html")]
+            struct Test;
+        "#;
+        let i = parse_str::<syn::DeriveInput>(src).unwrap();
+        let s = visit_derive(&i, &config);
+        assert_eq!(s.wrapped, true);
+    }
+
+    #[test]
+    fn test_mime() {
+        let src = r#"
+            #[derive(Template)]
+            #[template(src = "", ext = "hbs", mime = "text/html", charset = "utf-8")]
+            struct Test;
+        "#;
+        let i = parse_str::<syn::DeriveInput>(src).unwrap();
+        let config = Config::new("");
+        let s = visit_derive(&i, &config);
+        assert_eq!(s.mime, Some("text/html".to_owned()));
+        assert_eq!(s.charset, Some("utf-8".to_owned()));
+        assert_eq!(s.escaper, HTML_ESCAPER);
+
+        let src = r#"
+            #[derive(Template)]
+            #[template(src = "", ext = "tmpl")]
+            struct Test;
+        "#;
+        let i = parse_str::<syn::DeriveInput>(src).unwrap();
+        let config = Config::new("[mime]\ntmpl = \"text/html; charset=utf-8\"");
+        let s = visit_derive(&i, &config);
+        assert_eq!(s.wrapped, false);
+        assert_eq!(s.escaper, HTML_ESCAPER);
+    }
+
+    #[test]
+    fn test_minify() {
+        let config = Config::new("[main]\nminify = true");
+        let src = r#"
+            #[derive(Template)]
+            #[template(src = "", ext = "html")]
+            struct Test;
+        "#;
+        let i = parse_str::<syn::DeriveInput>(src).unwrap();
+        assert!(visit_derive(&i, &config).minify);
+
+        let src = r#"
+            #[derive(Template)]
+            #[template(src = "", ext = "txt")]
+            struct Test;
+        "#;
+        let i = parse_str::<syn::DeriveInput>(src).unwrap();
+        assert!(!visit_derive(&i, &config).minify);
+
+        let src = r#"
+            #[derive(Template)]
+            #[template(src = "", ext = "html", minify = false)]
+            struct Test;
+        "#;
+        let i = parse_str::<syn::DeriveInput>(src).unwrap();
+        assert!(!visit_derive(&i, &config).minify);
+    }
 }
@@ -102,7 +102,23 @@ impl<'a> FindEach<'a> {
 
                     self.on_path = parent;
                 }
-                Node::Raw(..) | Node::Lit(..) | Node::Comment(_) => (),
+                Node::Call(_, _, _, args) => {
+                    for arg in args {
+                        self.visit_expr(arg);
+                    }
+                }
+                Node::Trans(_, _, args) => {
+                    for (_, arg) in args {
+                        self.visit_expr(arg);
+                    }
+                }
+                Node::Raw(..)
+                | Node::Lit(..)
+                | Node::Comment(_)
+                | Node::Macro(..)
+                | Node::Import(..)
+                | Node::Asset(..)
+                | Node::Embed(..) => (),
             }
             if self.loop_var {
                 break;
@@ -226,6 +226,22 @@ impl<'a> Visit<'a> for Generator<'a> {
     ) {
         visit_attrs!(self, attrs);
 
+        // `self.field` of the template context
+        if let (
+            syn::Expr::Path(syn::ExprPath {
+                path, qself: None, ..
+            }),
+            syn::Member::Named(m),
+        ) = (&**base, member)
+        {
+            if path.segments.len() == 1
+                && path.segments[0].ident == "self"
+                && self.scp[0][0] == self.root
+            {
+                return self.write_field(&m.to_string());
+            }
+        }
+
         self.visit_expr(base);
         write!(self.buf_t, ".{}", quote!(#member)).unwrap();
     }
@@ -415,10 +431,11 @@ impl<'a> Visit<'a> for Generator<'a> {
             if ident.chars().all(|x| x.is_ascii_uppercase() || x.eq(&'_')) {
                 self.buf_t.write(&ident);
             } else if ident == "self" {
+                self.use_context();
                 self.buf_t.write(&self.scp[0][0]);
             } else if self.scp.iter().all(|v| v.iter().all(|e| ident.ne(e))) {
                 if self.on.is_empty() {
-                    write!(self.buf_t, "{}.{}", self.scp[0][0], ident).unwrap()
+                    self.write_field(ident)
                 } else {
                     if let Some(j) = self.on.iter().rev().find_map(|x| match x {
                         On::Each(j) => Some(j),
@@ -430,7 +447,7 @@ impl<'a> Visit<'a> for Generator<'a> {
 
                     match self.on.last() {
                         // self
-                        None => write!(self.buf_t, "{}.{}", self.scp[0][0], ident).unwrap(),
+                        None => self.write_field(ident),
                         Some(On::Each(j)) | Some(On::With(j)) => {
                             debug_assert!(self.scp.get(*j).is_some() && !self.scp[*j].is_empty());
                             return write!(self.buf_t, "{}.{}", self.scp[*j][0], ident).unwrap();
@@ -445,7 +462,7 @@ impl<'a> Visit<'a> for Generator<'a> {
                 if self.on.is_empty() {
                     panic!("use super at top");
                 } else if self.on.len() == j {
-                    write!(self.buf_t, "{}.{}", self.scp[0][0], ident).unwrap();
+                    self.write_field(&ident);
                 } else if j < self.on.len() {
                     match self.on[self.on.len() - j - 1] {
                         On::With(j) => {
@@ -620,6 +637,7 @@ impl<'a> Visit<'a> for Generator<'a> {
     }
 
     fn visit_macro(&mut self, i: &'a syn::Macro) {
+        self.use_context();
         self.buf_t.write(&quote!(#i));
     }
 
@@ -0,0 +1,365 @@
+use std::{collections::BTreeMap, fs, mem, path::Path};
+
+use wearte_config::I18n;
+
+mod plural;
+
+// Messages of a locale, gettext `.po` file
+#[derive(Debug)]
+pub(crate) struct Catalog {
+    pub locale: String,
+    // Rust expression of the plural form index, on `_n: u64`
+    pub plural: String,
+    // forms of each message, only one without `msgid_plural`
+    pub messages: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Catalogs {
+    // the default catalog is the first one
+    pub catalogs: Vec<Catalog>,
+}
+
+impl Catalogs {
+    pub fn default(&self) -> &Catalog {
+        &self.catalogs[0]
+    }
+
+    pub fn locales(&self) -> Vec<&str> {
+        self.catalogs.iter().map(|c| c.locale.as_str()).collect()
+    }
+}
+
+// Part of a message, `{name}` is an argument and `{{` a literal brace
+#[derive(Debug, PartialEq)]
+pub(crate) enum Part<'a> {
+    Lit(String),
+    Arg(&'a str),
+}
+
+pub(crate) fn load(i18n: &I18n) -> Catalogs {
+    let entries = fs::read_dir(&i18n.dir)
+        .unwrap_or_else(|_| panic!("message catalogs directory not found: {:?}", i18n.dir));
+    let mut paths: Vec<_> = entries
+        .map(|e| e.expect("valid directory entry").path())
+        .filter(|p| p.extension().is_some_and(|e| e == "po"))
+        .collect();
+    paths.sort();
+
+    let mut catalogs: Vec<Catalog> = paths.iter().map(|p| read_catalog(p)).collect();
+    let default = catalogs
+        .iter()
+        .position(|c| c.locale == i18n.default)
+        .unwrap_or_else(|| {
+            panic!(
+                "catalog of the default locale `{}` not found in {:?}",
+                i18n.default, i18n.dir
+            )
+        });
+    let default = catalogs.remove(default);
+    catalogs.insert(0, default);
+
+    Catalogs { catalogs }
+}
+
+fn read_catalog(path: &Path) -> Catalog {
+    let src = fs::read_to_string(path)
+        .unwrap_or_else(|_| panic!("unable to open message catalog {:?}", path));
+    let locale = path.file_stem().unwrap().to_str().unwrap().to_owned();
+    parse_po(&src)
+        .map(|(plural, messages)| Catalog {
+            locale,
+            plural,
+            messages,
+        })
+        .unwrap_or_else(|(line, e)| panic!("invalid message catalog {:?}:{}: {}", path, line, e))
+}
+
+// Entry of a `.po` file in progress
+#[derive(Default)]
+struct Entry {
+    // line of `msgid`
+    line: usize,
+    context: Option<String>,
+    id: Option<String>,
+    plural: Option<String>,
+    forms: Vec<(usize, String)>,
+    fuzzy: bool,
+}
+
+impl Entry {
+    // string continued by the next lines
+    fn last(&mut self, keyword: Keyword) -> &mut String {
+        match keyword {
+            Keyword::Ctxt => self.context.as_mut().unwrap(),
+            Keyword::Id => self.id.as_mut().unwrap(),
+            Keyword::Plural => self.plural.as_mut().unwrap(),
+            Keyword::Str => &mut self.forms.last_mut().unwrap().1,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Keyword {
+    Ctxt,
+    Id,
+    Plural,
+    Str,
+}
+
+type Messages = BTreeMap<String, Vec<String>>;
+
+// Plural expression and translated messages, untranslated and fuzzy entries are
+// left out. Messages are looked up by `msgid` alone, so an id repeated with another
+// `msgctxt` is an error. Errors are the line number and the cause.
+fn parse_po(src: &str) -> Result<(String, Messages), (usize, String)> {
+    let mut header = None;
+    let mut messages = BTreeMap::new();
+    let mut entry = Entry::default();
+    let mut last = None;
+    let mut entries = vec![];
+
+    for (n, line) in src.lines().enumerate() {
+        let line = line.trim();
+        let err = |e: &str| (n + 1, e.to_owned());
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('#') {
+            if entry.id.is_some() {
+                entries.push(mem::take(&mut entry));
+            }
+            if line.starts_with("#,") && line.contains("fuzzy") {
+                entry.fuzzy = true;
+            }
+            last = None;
+            continue;
+        }
+
+        if line.starts_with('"') {
+            let s = unquote(line).ok_or_else(|| err("invalid string"))?;
+            match last {
+                Some(keyword) => entry.last(keyword).push_str(&s),
+                None => return Err(err("string without keyword")),
+            }
+            continue;
+        }
+
+        let (keyword, rest) = line.split_at(line.find(' ').ok_or_else(|| err("missing string"))?);
+        let s = unquote(rest.trim()).ok_or_else(|| err("invalid string"))?;
+        match keyword {
+            "msgctxt" => {
+                if entry.id.is_some() {
+                    entries.push(mem::take(&mut entry));
+                }
+                entry.context = Some(s);
+                last = Some(Keyword::Ctxt);
+            }
+            "msgid" => {
+                if entry.id.is_some() {
+                    entries.push(mem::take(&mut entry));
+                }
+                entry.line = n + 1;
+                entry.id = Some(s);
+                last = Some(Keyword::Id);
+            }
+            "msgid_plural" => {
+                entry.plural = Some(s);
+                last = Some(Keyword::Plural);
+            }
+            "msgstr" => {
+                entry.forms.push((0, s));
+                last = Some(Keyword::Str);
+            }
+            k if k.starts_with("msgstr[") && k.ends_with(']') => {
+                let i = k[7..k.len() - 1]
+                    .parse()
+                    .map_err(|_| err("invalid plural index"))?;
+                entry.forms.push((i, s));
+                last = Some(Keyword::Str);
+            }
+            k => return Err(err(&format!("unknown keyword `{}`", k))),
+        }
+    }
+    if entry.id.is_some() {
+        entries.push(entry);
+    }
+
+    let mut lines = BTreeMap::new();
+    for mut entry in entries {
+        let id = entry.id.unwrap();
+        if id.is_empty() {
+            header = entry.forms.pop().map(|f| f.1);
+            continue;
+        }
+        if let Some(first) = lines.insert(id.clone(), entry.line) {
+            let cause = match entry.context {
+                Some(ctxt) => format!(
+                    "message `{}` is already defined at line {}, `msgctxt \"{}\"` doesn't tell \
+                     them apart, messages are looked up by `msgid` alone",
+                    id, first, ctxt
+                ),
+                None => format!("message `{}` is already defined at line {}", id, first),
+            };
+            return Err((entry.line, cause));
+        }
+        entry.forms.sort_by_key(|f| f.0);
+        let forms: Vec<String> = entry.forms.into_iter().map(|f| f.1).collect();
+        if entry.fuzzy || forms.iter().all(String::is_empty) {
+            continue;
+        }
+        messages.insert(id, forms);
+    }
+
+    let plural = header
+        .as_ref()
+        .and_then(|h| {
+            h.lines()
+                .find(|l| l.trim_start().starts_with("Plural-Forms:"))
+                .and_then(|l| l.split("plural=").nth(1))
+        })
+        .map(|p| p.trim().trim_end_matches(';'))
+        .unwrap_or("n != 1");
+    let plural = plural::compile(plural).map_err(|e| (1, format!("in Plural-Forms, {}", e)))?;
+
+    Ok((plural, messages))
+}
+
+// Content of a C string literal
+fn unquote(s: &str) -> Option<String> {
+    if s.len() < 2 || !s.starts_with('"') || !s.ends_with('"') {
+        return None;
+    }
+
+    let mut out = String::new();
+    let mut chars = s[1..s.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            out.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                c @ '"' | c @ '\\' => c,
+                _ => return None,
+            });
+        } else {
+            out.push(c);
+        }
+    }
+
+    Some(out)
+}
+
+pub(crate) fn parts(msg: &str) -> Vec<Part<'_>> {
+    let mut parts = vec![];
+    let mut lit = String::new();
+    let mut rest = msg;
+    while let Some(j) = rest.find(['{', '}']) {
+        lit.push_str(&rest[..j]);
+        let (brace, after) = rest[j..].split_at(1);
+        if after.starts_with(brace) {
+            lit.push_str(brace);
+            rest = &after[1..];
+            continue;
+        }
+
+        let arg = if brace == "{" {
+            after.find('}').map(|k| &after[..k]).filter(|a| {
+                a.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                    && a.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            })
+        } else {
+            None
+        };
+        match arg {
+            Some(arg) => {
+                if !lit.is_empty() {
+                    parts.push(Part::Lit(mem::take(&mut lit)));
+                }
+                parts.push(Part::Arg(arg));
+                rest = &after[arg.len() + 1..];
+            }
+            None => {
+                lit.push_str(brace);
+                rest = after;
+            }
+        }
+    }
+    lit.push_str(rest);
+    if !lit.is_empty() {
+        parts.push(Part::Lit(lit));
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_po() {
+        let src = r#"
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=2; plural=(n != 1);\n"
+
+# greeting
+msgid "hello"
+msgstr "Hallo, {name}!"
+
+msgid "cart.items"
+msgid_plural "cart.items"
+msgstr[1] "{count} Artikel"
+msgstr[0] "Ein "
+"Artikel"
+
+msgid "untranslated"
+msgstr ""
+
+#, fuzzy
+msgid "fuzzy"
+msgstr "Unscharf"
+
+msgctxt "punctuation"
+msgid "quote"
+msgstr "\"a\"\tb\n"
+"#;
+        let (plural, messages) = parse_po(src).unwrap();
+        assert_eq!(plural, "((_n != 1u64) as u64)");
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages["hello"], vec!["Hallo, {name}!"]);
+        assert_eq!(
+            messages["cart.items"],
+            vec!["Ein Artikel", "{count} Artikel"]
+        );
+        assert_eq!(messages["quote"], vec!["\"a\"\tb\n"]);
+
+        assert_eq!(parse_po("msgid \"a\"\nmsgstr b").unwrap_err().0, 2);
+        assert_eq!(parse_po("msgid \"a\"\nfoo \"b\"").unwrap_err().0, 2);
+
+        let repeated = "msgctxt \"menu\"\nmsgid \"open\"\nmsgstr \"Öffnen\"\n\n\
+                        msgctxt \"\"\n\"state\"\nmsgid \"open\"\nmsgstr \"Offen\"";
+        let (line, cause) = parse_po(repeated).unwrap_err();
+        assert_eq!(line, 7);
+        assert!(
+            cause.starts_with("message `open` is already defined at line 2, `msgctxt \"state\"`")
+        );
+    }
+
+    #[test]
+    fn test_parts() {
+        assert_eq!(
+            parts("Hello, {name}! {{literal}} {1} {"),
+            vec![
+                Part::Lit("Hello, ".into()),
+                Part::Arg("name"),
+                Part::Lit("! {literal} {1} {".into()),
+            ]
+        );
+        assert_eq!(parts("{count}"), vec![Part::Arg("count")]);
+        assert_eq!(parts(""), vec![]);
+    }
+}
@@ -0,0 +1,161 @@
+// Rust expression of a gettext `plural=` formula, a C expression on `n`.
+// Every value is an `u64` named `_n` in the generated code, comparisons and
+// logical operators give 0 or 1 as in C.
+pub(crate) fn compile(src: &str) -> Result<String, String> {
+    let tokens = tokenize(src)?;
+    let mut p = Parser { tokens, at: 0 };
+    let expr = p.ternary()?;
+    match p.tokens.get(p.at) {
+        None => Ok(expr),
+        Some(t) => Err(format!("unexpected `{}`", t)),
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<String>, String> {
+    let mut tokens = vec![];
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            '0'..='9' => {
+                let mut num = c.to_string();
+                while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    num.push(*d);
+                    chars.next();
+                }
+                tokens.push(num);
+            }
+            'n' | '?' | ':' | '(' | ')' | '+' | '-' | '*' | '/' | '%' => tokens.push(c.to_string()),
+            '=' | '!' | '<' | '>' | '&' | '|' => {
+                let mut op = c.to_string();
+                if let Some(&d) = chars.peek() {
+                    if d == '=' && c != '&' && c != '|' || d == c && (c == '&' || c == '|') {
+                        op.push(d);
+                        chars.next();
+                    }
+                }
+                if op == "=" || op == "&" || op == "|" {
+                    return Err(format!("unsupported operator `{}`", op));
+                }
+                tokens.push(op);
+            }
+            c => return Err(format!("unexpected `{}`", c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    at: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.at).map(String::as_str)
+    }
+
+    fn eat(&mut self, t: &str) -> bool {
+        if self.peek() == Some(t) {
+            self.at += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ternary(&mut self) -> Result<String, String> {
+        let cond = self.binary(0)?;
+        if !self.eat("?") {
+            return Ok(cond);
+        }
+        let then = self.ternary()?;
+        if !self.eat(":") {
+            return Err("expected `:`".to_owned());
+        }
+        let els = self.ternary()?;
+
+        Ok(format!(
+            "(if {} != 0 {{ {} }} else {{ {} }})",
+            cond, then, els
+        ))
+    }
+
+    // operators by precedence, lowest first
+    fn binary(&mut self, level: usize) -> Result<String, String> {
+        const LEVELS: &[&[&str]] = &[
+            &["||"],
+            &["&&"],
+            &["==", "!="],
+            &["<", ">", "<=", ">="],
+            &["+", "-"],
+            &["*", "/", "%"],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+
+        let mut left = self.binary(level + 1)?;
+        while let Some(op) = self.peek().filter(|t| LEVELS[level].contains(t)) {
+            let op = op.to_owned();
+            self.at += 1;
+            let right = self.binary(level + 1)?;
+            left = match op.as_str() {
+                "||" | "&&" => format!("((({} != 0) {} ({} != 0)) as u64)", left, op, right),
+                "+" | "-" | "*" | "/" | "%" => format!("({} {} {})", left, op, right),
+                _ => format!("(({} {} {}) as u64)", left, op, right),
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<String, String> {
+        if self.eat("!") {
+            return Ok(format!("(({} == 0) as u64)", self.unary()?));
+        }
+        if self.eat("(") {
+            let expr = self.ternary()?;
+            if !self.eat(")") {
+                return Err("expected `)`".to_owned());
+            }
+            return Ok(expr);
+        }
+
+        match self.peek() {
+            Some("n") => {
+                self.at += 1;
+                Ok("_n".to_owned())
+            }
+            Some(num) if num.bytes().all(|b| b.is_ascii_digit()) => {
+                let num = format!("{}u64", num);
+                self.at += 1;
+                Ok(num)
+            }
+            Some(t) => Err(format!("unexpected `{}`", t)),
+            None => Err("unexpected end".to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile() {
+        assert_eq!(compile("0").unwrap(), "0u64");
+        assert_eq!(compile("(n != 1)").unwrap(), "((_n != 1u64) as u64)");
+        assert_eq!(compile("!n").unwrap(), "((_n == 0) as u64)");
+        assert_eq!(
+            compile("n==1 ? 0 : n%10>=2 && n%10<=4 ? 1 : 2").unwrap(),
+            "(if ((_n == 1u64) as u64) != 0 { 0u64 } else { \
+             (if ((((((_n % 10u64) >= 2u64) as u64) != 0) && ((((_n % 10u64) <= 4u64) as u64) != 0)) as u64) != 0 \
+             { 1u64 } else { 2u64 }) })"
+        );
+        assert!(compile("n = 1").is_err());
+        assert!(compile("(n").is_err());
+        assert!(compile("n 1").is_err());
+    }
+}
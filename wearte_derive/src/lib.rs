@@ -4,9 +4,14 @@
 //! that goes together. With this, wearte will parse the file and the struct to create an **ast**,
 //! and will provide the user's struct the functionality of `fmt` for the template.
 //! Derivation is implements `fmt::Display`, superTrait of `Template`, and if activated,
-//! `actix_web::Responder` will be implemented on the user's struct.
+//! `actix_web::Responder` or `axum::response::IntoResponse` will be implemented on the user's
+//! struct.
 //! `Template` is defined in the main wearte crate and implements `fmt` in functions like
-//! `call`, `call_into_fmt`, `call_into_io`, `mime `, and `size_hint `.
+//! `call`, `call_into_fmt`, `call_into_io`, `mime `, and `size_hint `, and the object safe
+//! `dyn_mime` and `dyn_size_hint` for `dyn Template`, only generated on enums, where they
+//! depend on the variant.
+//! On enums every variant has its own `#[template(...)]` with the fields of the variant in scope,
+//! or a single field that already implements `Template`.
 //!
 extern crate proc_macro;
 
@@ -15,70 +20,270 @@ extern crate nom;
 #[macro_use]
 extern crate quote;
 
+mod assets;
 mod generator;
+mod i18n;
 mod logger;
 mod parser;
 
 use proc_macro::TokenStream;
+use proc_macro2::{Group, TokenStream as TokenStream2, TokenTree};
 use syn;
 
-use std::collections::BTreeMap;
+use std::{fmt::Write, path::PathBuf};
 
 use wearte_config::{read_config_file, Config};
 
-use crate::generator::{visit_derive, Print};
+use crate::generator::{
+    visit_derive, visit_enum, visit_locales, Enum, Locales, Parsed, Print, SourceMap, Struct,
+};
 use crate::logger::log;
-use crate::parser::{parse, parse_partials, Node};
-use wearte_config::PrintConfig;
+use crate::parser::{parse, parse_partials, resolve_markers, strip_standalone, Node};
+use wearte_config::{PrintConfig, Whitespace};
 
 #[proc_macro_derive(Template, attributes(template))]
 pub fn derive(input: TokenStream) -> TokenStream {
-    build(&syn::parse(input).unwrap())
+    let i = syn::parse(input).unwrap();
+    respan_unused(build(&i).parse().unwrap(), &named_fields(&i)).into()
 }
 
+// overlays of a single template, it's generated once per combination of them
+const MAX_OVERLAYS: usize = 4;
+
 #[inline]
-fn build(i: &syn::DeriveInput) -> TokenStream {
+fn build(i: &syn::DeriveInput) -> String {
     let config_toml: &str = &read_config_file();
     let config = &Config::new(config_toml);
 
-    let s = visit_derive(i, &config);
+    // overlays replacing a file of the template, every combination of their
+    // features has its own implementation
+    let paths = source_paths(i, config);
+    let overlays: Vec<&str> = config
+        .get_overlays()
+        .into_iter()
+        .filter(|f| source_paths(i, &Config::new(config_toml).with_overlays(&[f])) != paths)
+        .collect();
+    if overlays.is_empty() {
+        return generate(i, config);
+    }
+    if MAX_OVERLAYS < overlays.len() {
+        panic!(
+            "template of {} is overridden by {} overlays ({}), {} at most are supported",
+            i.ident,
+            overlays.len(),
+            overlays.join(", "),
+            MAX_OVERLAYS
+        );
+    }
 
-    let mut sources = BTreeMap::new();
+    let mut code = String::new();
+    for n in 0..1usize << overlays.len() {
+        let on: Vec<&str> = (0..overlays.len())
+            .filter(|j| n >> j & 1 == 1)
+            .map(|j| overlays[j])
+            .collect();
+        let config = &Config::new(config_toml).with_overlays(&on);
 
-    let mut check = vec![(s.path.clone(), s.src.clone())];
-    while let Some((path, src)) = check.pop() {
-        for n in &parse_partials(&src) {
-            match n {
-                Node::Partial(_, partial, _) => {
-                    check.push(config.get_partial(&path, partial));
+        let features: Vec<String> = overlays
+            .iter()
+            .map(|f| {
+                if on.contains(f) {
+                    format!("feature = {:?}", f)
+                } else {
+                    format!("not(feature = {:?})", f)
                 }
-                _ => unreachable!(),
+            })
+            .collect();
+        writeln!(
+            code,
+            "#[cfg(all({}))]\nconst _: () = {{\n{}\n}};",
+            features.join(", "),
+            generate(i, config)
+        )
+        .unwrap();
+    }
+
+    code
+}
+
+// The constants of `warn_unused` take the span of their field, rustc doesn't
+// report the use of a deprecated item inside the expansion of the derive
+fn respan_unused(tokens: TokenStream2, fields: &[&syn::Ident]) -> TokenStream2 {
+    tokens
+        .into_iter()
+        .map(|t| match t {
+            TokenTree::Group(g) => {
+                let mut group = Group::new(g.delimiter(), respan_unused(g.stream(), fields));
+                group.set_span(g.span());
+                TokenTree::Group(group)
             }
+            TokenTree::Ident(mut ident) => {
+                let name = ident.to_string();
+                if let Some(field) = name
+                    .strip_prefix("__wearte_unused_")
+                    .and_then(|n| fields.iter().find(|f| **f == n))
+                {
+                    ident.set_span(field.span());
+                }
+                TokenTree::Ident(ident)
+            }
+            t => t,
+        })
+        .collect()
+}
+
+fn named_fields(i: &syn::DeriveInput) -> Vec<&syn::Ident> {
+    let fields: Vec<&syn::Fields> = match i.data {
+        syn::Data::Struct(ref data) => vec![&data.fields],
+        syn::Data::Enum(ref data) => data.variants.iter().map(|v| &v.fields).collect(),
+        syn::Data::Union(_) => vec![],
+    };
+    fields
+        .into_iter()
+        .flat_map(|f| f.iter().filter_map(|f| f.ident.as_ref()))
+        .collect()
+}
+
+enum Visited<'a> {
+    Enum(Enum<'a>),
+    Locales(Locales<'a>),
+    Struct(Box<Struct<'a>>),
+}
+
+impl<'a> Visited<'a> {
+    fn templates(&self) -> Vec<&Struct<'a>> {
+        match self {
+            Visited::Enum(e) => e
+                .variants
+                .iter()
+                .filter_map(|v| v.template.as_ref())
+                .collect(),
+            Visited::Locales(l) => l.templates.iter().map(|(_, s)| s).collect(),
+            Visited::Struct(s) => vec![s.as_ref()],
+        }
+    }
+}
+
+fn visit<'a>(i: &'a syn::DeriveInput, config: &'a Config) -> Visited<'a> {
+    match i.data {
+        syn::Data::Enum(ref data) => {
+            let e = visit_enum(i, data, config);
+            if e.variants.iter().all(|v| v.template.is_none()) {
+                panic!(
+                    "enum {} needs at least a variant with a template attribute",
+                    e.ident
+                );
+            }
+            Visited::Enum(e)
         }
-        sources.insert(path, src);
+        _ => match visit_locales(i, config) {
+            Some(l) => Visited::Locales(l),
+            None => Visited::Struct(Box::new(visit_derive(i, config))),
+        },
     }
+}
 
-    let mut parsed = BTreeMap::new();
-    for (p, src) in &sources {
-        parsed.insert(p, parse(src));
+// Files of the template, its partials and the embedded ones
+fn source_paths(i: &syn::DeriveInput, config: &Config) -> Vec<PathBuf> {
+    let v = visit(i, config);
+    let sources = read_sources(config, &v.templates());
+    let mut paths: Vec<PathBuf> = sources.values().flat_map(|s| s.keys().cloned()).collect();
+    for src in sources.values().flat_map(|s| s.values()) {
+        for n in &parse_partials(src) {
+            if let Node::Embed(_, path, _) = n {
+                paths.extend(config.find_file(path));
+            }
+        }
+    }
+    paths.sort();
+    paths.dedup();
+
+    paths
+}
+
+fn generate(i: &syn::DeriveInput, config: &Config) -> String {
+    let v = visit(i, config);
+    let templates = v.templates();
+
+    let sources = read_sources(config, &templates);
+    let parsed = parse_sources(config, &sources, &templates);
+
+    let code = match v {
+        Visited::Enum(ref e) => generator::generate_enum(config, e, &parsed, &sources),
+        Visited::Locales(ref l) => generator::generate_locales(config, l, &parsed, &sources),
+        Visited::Struct(ref s) => generator::generate(config, s, &parsed, &sources),
+    };
+    log_code(config, &code, &templates);
+
+    code
+}
+
+// Sources of the templates and all their partials, by whitespace mode and
+// `trim_trailing` of the templates reading them
+fn read_sources(config: &Config, templates: &[&Struct]) -> SourceMap {
+    let mut all = SourceMap::new();
+
+    for s in templates {
+        let sources = all.entry((s.whitespace, s.trim_trailing)).or_default();
+        let mut check = vec![(s.path.clone(), s.src.clone())];
+        while let Some((path, mut src)) = check.pop() {
+            // imported more than once, or by itself
+            if sources.contains_key(&path) {
+                continue;
+            }
+            if s.whitespace == Whitespace::Standalone {
+                src = strip_standalone(&src);
+            }
+            src = resolve_markers(&src, s.whitespace == Whitespace::Suppress);
+
+            for n in &parse_partials(&src) {
+                match n {
+                    Node::Partial(_, partial, _) | Node::Import(_, partial, _) => {
+                        check.push(config.get_partial(&path, partial, s.trim_trailing));
+                    }
+                    Node::Call(..) | Node::Embed(..) => (),
+                    _ => unreachable!(),
+                }
+            }
+            sources.insert(path, src);
+        }
+    }
+
+    all
+}
+
+fn parse_sources<'a>(config: &Config, all: &'a SourceMap, templates: &[&Struct]) -> Parsed<'a> {
+    let mut parsed = Parsed::new();
+    for (mode, sources) in all {
+        let parsed = parsed.entry(*mode).or_default();
+        for (p, src) in sources {
+            parsed.insert(p, parse(src));
+        }
     }
 
     if config.print_override == PrintConfig::Ast
         || config.print_override == PrintConfig::All
-        || s.print == Print::Ast
-        || s.print == Print::All
+        || templates
+            .iter()
+            .any(|s| s.print == Print::Ast || s.print == Print::All)
     {
         eprintln!("{:?}\n", parsed);
     }
 
-    let code = generator::generate(&config, &s, &parsed);
-    if config.print_override == PrintConfig::Code
-        || config.print_override == PrintConfig::All
-        || s.print == Print::Code
-        || s.print == Print::All
-    {
-        log(&code, s.path.to_str().unwrap().to_owned(), &config.debug);
-    }
+    parsed
+}
 
-    code.parse().unwrap()
+fn log_code(config: &Config, code: &str, templates: &[&Struct]) {
+    let print = templates
+        .iter()
+        .find(|s| s.print == Print::Code || s.print == Print::All);
+    if config.print_override == PrintConfig::Code || config.print_override == PrintConfig::All {
+        log(
+            code,
+            templates[0].path.to_str().unwrap().to_owned(),
+            &config.debug,
+        );
+    } else if let Some(s) = print {
+        log(code, s.path.to_str().unwrap().to_owned(), &config.debug);
+    }
 }
@@ -0,0 +1,100 @@
+use super::standalone::is_raw_tag;
+
+// Whitespace markers of the block tags, helpers (`{{#each}}`, `{{else}}`, `{{/each}}`...),
+// macros and imports. With `whitespace = "suppress"` a side without marker gets a `~`.
+// The keep marker `+` is removed in any mode, it only stops the `~` of `suppress`
+// and the standalone lines, e.g. `{{+#each items +}}` keeps the whitespace around it.
+// Raw blocks `{{R}}...{{/R}}` are not touched.
+pub(crate) fn resolve_markers(src: &str, suppress: bool) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut raw = false;
+    let mut rest = src;
+    while let Some(i) = rest.find("{{") {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 2..];
+        out.push_str("{{");
+
+        let tag = rest.trim_start_matches('~');
+        if raw {
+            raw = !is_raw_tag(tag, "/R");
+            continue;
+        }
+        raw = is_raw_tag(tag, "R");
+
+        let end = match rest.find("}}") {
+            Some(end) => end,
+            None => break,
+        };
+        let inner = &rest[..end];
+        if !is_block(inner.strip_prefix('+').unwrap_or(inner)) {
+            continue;
+        }
+
+        let inner = match inner.as_bytes().first() {
+            Some(b'~') => inner,
+            Some(b'+') => &inner[1..],
+            _ if suppress => {
+                out.push('~');
+                inner
+            }
+            _ => inner,
+        };
+        let trimmed = inner.trim_end();
+        if let Some(keep) = trimmed.strip_suffix('+') {
+            out.push_str(keep);
+            out.push_str(&inner[trimmed.len()..]);
+        } else {
+            out.push_str(inner);
+            if suppress && !trimmed.ends_with('~') {
+                out.push('~');
+            }
+        }
+        out.push_str("}}");
+        rest = &rest[end + 2..];
+    }
+    out.push_str(rest);
+
+    out
+}
+
+// Content of a block tag, after the left marker
+fn is_block(tag: &str) -> bool {
+    let tag = tag.strip_prefix('~').unwrap_or(tag);
+    match tag.as_bytes().first() {
+        Some(b'#') => true,
+        Some(b'/') => !is_raw_tag(tag, "/R"),
+        _ => tag
+            .trim_start()
+            .strip_prefix("else")
+            .is_some_and(|t| !t.starts_with(|c: char| c.is_alphanumeric() || c == '_')),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suppress() {
+        let src = "{{#each items}}\n{{ key }}\n{{~ else +}}\n{{+/each}}";
+        assert_eq!(
+            resolve_markers(src, true),
+            "{{~#each items~}}\n{{ key }}\n{{~ else }}\n{{/each~}}"
+        );
+        assert_eq!(
+            resolve_markers("{{#if a~}}{{ elsewhere }}{{~/if}}", true),
+            "{{~#if a~}}{{ elsewhere }}{{~/if~}}"
+        );
+        assert_eq!(
+            resolve_markers("{{R}}{{#if a}}{{/R}}{{{ b }}}{{> c }}", true),
+            "{{R}}{{#if a}}{{/R}}{{{ b }}}{{> c }}"
+        );
+    }
+
+    #[test]
+    fn test_keep() {
+        let src = "{{+#if a +}}\n{{else}}{{/if}}";
+        assert_eq!(resolve_markers(src, false), "{{#if a }}\n{{else}}{{/if}}");
+        assert_eq!(resolve_markers("a {{ b + }}", false), "a {{ b + }}");
+    }
+}
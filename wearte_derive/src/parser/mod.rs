@@ -1,14 +1,19 @@
 use memchr::memchr;
 use nom;
-use syn::{parse_str, Expr, Stmt};
+use syn::{parse_str, Expr, ExprCall, Stmt, Type};
 
 use std::str::{self, from_utf8};
 
+mod markers;
 mod pre_partials;
+mod standalone;
 
+pub(crate) use self::markers::resolve_markers;
 pub(crate) use self::pre_partials::parse_partials;
+pub(crate) use self::standalone::strip_standalone;
 
 pub(crate) type Ws = (bool, bool);
+pub(crate) type Params<'a> = Vec<(&'a str, Option<Type>)>;
 
 #[derive(Debug, PartialEq)]
 pub(crate) enum Node<'a> {
@@ -20,6 +25,31 @@ pub(crate) enum Node<'a> {
     Partial(Ws, &'a str, Vec<Expr>),
     Raw((Ws, Ws), &'a str, &'a str, &'a str),
     Safe(Ws, Expr),
+    // {{#macro name(params)}}, parameters with optional type
+    Macro((Ws, Ws), &'a str, Params<'a>, Vec<Node<'a>>),
+    // {{#import "path" as alias}}
+    Import(Ws, &'a str, &'a str),
+    // {{> alias::name(args) }}
+    Call(Ws, Option<&'a str>, &'a str, Vec<Expr>),
+    // {{ t "key" name=expr }}
+    Trans(Ws, &'a str, Vec<(&'a str, Expr)>),
+    // {{ asset "path" }} or {{ integrity "path" }}
+    Asset(Ws, Asset, &'a str),
+    // {{> raw "path" escape }}, file embedded without parsing it
+    Embed(Ws, &'a str, Encoding),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Asset {
+    Url,
+    Integrity,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Encoding {
+    Verbatim,
+    Escape,
+    Base64,
 }
 
 #[derive(Debug, PartialEq)]
@@ -45,6 +75,11 @@ const ERR_IF: nom::ErrorKind = nom::ErrorKind::Custom(5);
 const ERR_LOCAL: nom::ErrorKind = nom::ErrorKind::Custom(6);
 const ERR_PARTIAL: nom::ErrorKind = nom::ErrorKind::Custom(7);
 const ERR_RAW: nom::ErrorKind = nom::ErrorKind::Custom(8);
+const ERR_MACRO: nom::ErrorKind = nom::ErrorKind::Custom(9);
+const ERR_IMPORT: nom::ErrorKind = nom::ErrorKind::Custom(10);
+const ERR_TRANS: nom::ErrorKind = nom::ErrorKind::Custom(11);
+const ERR_ASSET: nom::ErrorKind = nom::ErrorKind::Custom(12);
+const ERR_EMBED: nom::ErrorKind = nom::ErrorKind::Custom(13);
 
 pub(crate) fn parse(src: &str) -> Vec<Node> {
     match eat(Input(src.as_bytes())) {
@@ -70,6 +105,11 @@ pub(crate) fn parse(src: &str) -> Vec<Node> {
                 ERR_IF => panic!("problems parsing helper IF: {:?}", err),
                 ERR_LOCAL => panic!("problems parsing LET block: {:?}", err),
                 ERR_PARTIAL => panic!("problems parsing partial: {:?}", err),
+                ERR_MACRO => panic!("problems parsing macro: {:?}", err),
+                ERR_IMPORT => panic!("problems parsing import: {:?}", err),
+                ERR_TRANS => panic!("problems parsing translation: {:?}", err),
+                ERR_ASSET => panic!("problems parsing asset: {:?}", err),
+                ERR_EMBED => panic!("problems parsing raw partial: {:?}", err),
                 _ => panic!("problems parsing template source: {:?}", err),
             }
         }
@@ -242,6 +282,31 @@ macro_rules! try_eat_local {
     };
 }
 
+static TRANS: &[u8] = b"t ";
+macro_rules! try_eat_trans {
+    ($c:ident, $s:ident, $ws:expr) => {
+        if $s.0.starts_with(TRANS) {
+            if let Some(t) = trans(Input(&$s[TRANS.len()..])) {
+                return t.map(|(key, args)| ($c, Node::Trans($ws, key, args)));
+            }
+        }
+    };
+}
+
+static ASSET: &[u8] = b"asset ";
+static INTEGRITY: &[u8] = b"integrity ";
+macro_rules! try_eat_asset {
+    ($c:ident, $s:ident, $ws:expr) => {
+        for (tag, kind) in &[(ASSET, Asset::Url), (INTEGRITY, Asset::Integrity)] {
+            if $s.0.starts_with(tag) {
+                if let Some(path) = asset(Input(&$s[tag.len()..])) {
+                    return path.map(|path| ($c, Node::Asset($ws, *kind, path)));
+                }
+            }
+        }
+    };
+}
+
 macro_rules! map_failure {
     ($i:expr, $e:ident, $($t:tt)+) => {
         ($($t)+).map_err(|_| nom::Err::Failure(error_position!($i, $e)))
@@ -249,6 +314,15 @@ macro_rules! map_failure {
 }
 
 fn partial(i: Input, lws: bool) -> Result<(Input, Node), nom::Err<Input>> {
+    match embed(i, lws) {
+        Err(nom::Err::Error(_)) => (),
+        res => return res,
+    }
+    match call(i, lws) {
+        Err(nom::Err::Error(_)) => (),
+        res => return res,
+    }
+
     let (i, ident) = do_parse!(
         i,
         take_while!(ws) >> ident: path >> take_while!(ws) >> (ident)
@@ -270,24 +344,48 @@ fn partial(i: Input, lws: bool) -> Result<(Input, Node), nom::Err<Input>> {
 }
 
 fn helper(i: Input, a_lws: bool) -> Result<(Input, Node), nom::Err<Input>> {
-    let (i, (above_ws, ident, args)) = do_parse!(
-        i,
-        take_while!(ws)
-            >> ident: identifier
-            >> args: arguments
+    let (c, ident) = do_parse!(i, take_while!(ws) >> ident: identifier >> (ident))?;
+    match ident {
+        "macro" => return macro_def(c, a_lws),
+        "import" => return import(i, a_lws),
+        _ => (),
+    }
+
+    let (i, (above_ws, args)) = do_parse!(
+        c,
+        args: arguments
             >> take_while!(ws)
             >> rws: opt!(tag!("~"))
             >> tag!("}}")
-            >> (((a_lws, rws.is_some()), ident, args))
+            >> (((a_lws, rws.is_some()), args))
     )?;
 
     if ident.eq("if") {
         return if_else(above_ws, i, args);
     }
 
+    let (c, (below_ws, block)) = block_end(i, ident, ERR_HELPER)?;
+    Ok((
+        c,
+        Node::Helper({
+            match ident {
+                "each" => Helper::Each((above_ws, below_ws), args, block),
+                "with" => Helper::With((above_ws, below_ws), args, block),
+                "unless" => Helper::Unless((above_ws, below_ws), args, block),
+                defined => Helper::Defined((above_ws, below_ws), defined, args, block),
+            }
+        }),
+    ))
+}
+
+type Block<'a> = Result<(Input<'a>, (Ws, Vec<Node<'a>>)), nom::Err<Input<'a>>>;
+
+// Block of a helper until its closing tag `{{/ident}}`
+fn block_end<'a>(i: Input<'a>, ident: &str, err: nom::ErrorKind) -> Block<'a> {
+    let kind = err.clone();
     let (c, (below_ws, block, c_ident)) = map_failure!(
         i,
-        ERR_HELPER,
+        kind,
         do_parse!(
             i,
             block: eat
@@ -303,20 +401,167 @@ fn helper(i: Input, a_lws: bool) -> Result<(Input, Node), nom::Err<Input>> {
     )?;
 
     if ident.eq(c_ident) {
-        Ok((
-            c,
-            Node::Helper({
-                match ident {
-                    "each" => Helper::Each((above_ws, below_ws), args, block),
-                    "with" => Helper::With((above_ws, below_ws), args, block),
-                    "unless" => Helper::Unless((above_ws, below_ws), args, block),
-                    defined => Helper::Defined((above_ws, below_ws), defined, args, block),
-                }
-            }),
-        ))
+        Ok((c, (below_ws, block)))
     } else {
-        Err(nom::Err::Failure(error_position!(i, ERR_HELPER)))
+        Err(nom::Err::Failure(error_position!(i, err)))
+    }
+}
+
+fn macro_def(i: Input, a_lws: bool) -> Result<(Input, Node), nom::Err<Input>> {
+    let (i, (name, params, above_ws)) = map_failure!(
+        i,
+        ERR_MACRO,
+        do_parse!(
+            i,
+            take_while1!(ws)
+                >> name: identifier
+                >> take_while!(ws)
+                >> tag!("(")
+                >> params: take_until!(")")
+                >> tag!(")")
+                >> take_while!(ws)
+                >> rws: opt!(tag!("~"))
+                >> tag!("}}")
+                >> ((name, params, (a_lws, rws.is_some())))
+        )
+    )?;
+    let params = map_failure!(i, ERR_MACRO, eat_params(params))?;
+
+    let (c, (below_ws, block)) = block_end(i, "macro", ERR_MACRO)?;
+    Ok((c, Node::Macro((above_ws, below_ws), name, params, block)))
+}
+
+// `name` or `name: Type`, separated by commas
+fn eat_params(i: Input) -> Result<Params, ()> {
+    let mut params = vec![];
+    let (_, list, _) = trim(i);
+    if list.0.is_empty() {
+        return Ok(params);
+    }
+
+    for param in split_params(safe_utf8(list.0)) {
+        let (name, ty) = match param.find(':') {
+            Some(j) => (
+                &param[..j],
+                Some(parse_str::<Type>(&param[j + 1..]).map_err(|_| ())?),
+            ),
+            None => (param, None),
+        };
+        let name = name.trim();
+        match name.as_bytes().first() {
+            Some(c) if nom::is_alphabetic(*c) || *c == b'_' => (),
+            _ => return Err(()),
+        }
+        if !name.bytes().all(|c| nom::is_alphanumeric(c) || c == b'_') {
+            return Err(());
+        }
+        params.push((name, ty));
+    }
+
+    Ok(params)
+}
+
+// Commas inside the generics of a type don't separate parameters
+fn split_params(list: &str) -> Vec<&str> {
+    let mut params = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (j, c) in list.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                params.push(&list[start..j]);
+                start = j + 1;
+            }
+            _ => (),
+        }
     }
+    params.push(&list[start..]);
+
+    params
+}
+
+pub(crate) fn import(i: Input, lws: bool) -> Result<(Input, Node), nom::Err<Input>> {
+    let (i, _) = do_parse!(
+        i,
+        take_while!(ws) >> tag!("import") >> take_while1!(ws) >> ()
+    )?;
+    map_failure!(
+        i,
+        ERR_IMPORT,
+        do_parse!(
+            i,
+            tag!("\"")
+                >> path: take_until!("\"")
+                >> tag!("\"")
+                >> take_while1!(ws)
+                >> tag!("as")
+                >> take_while1!(ws)
+                >> alias: identifier
+                >> take_while!(ws)
+                >> rws: opt!(tag!("~"))
+                >> tag!("}}")
+                >> (Node::Import((lws, rws.is_some()), safe_utf8(&path), alias))
+        )
+    )
+}
+
+// Partial tag with a file embedded as is, escaped or encoded in base64
+fn embed(i: Input, lws: bool) -> Result<(Input, Node), nom::Err<Input>> {
+    let (i, _) = do_parse!(
+        i,
+        take_while!(ws) >> tag!("raw") >> take_while1!(ws) >> peek!(tag!("\"")) >> ()
+    )?;
+    let (c, (path, encoding, rws)) = map_failure!(
+        i,
+        ERR_EMBED,
+        do_parse!(
+            i,
+            tag!("\"")
+                >> path: take_until!("\"")
+                >> tag!("\"")
+                >> take_while!(ws)
+                >> encoding: opt!(terminated!(identifier, take_while!(ws)))
+                >> rws: opt!(tag!("~"))
+                >> tag!("}}")
+                >> ((safe_utf8(&path), encoding, rws.is_some()))
+        )
+    )?;
+    let encoding = match encoding {
+        None => Encoding::Verbatim,
+        Some("escape") => Encoding::Escape,
+        Some("base64") => Encoding::Base64,
+        Some(_) => return Err(nom::Err::Failure(error_position!(i, ERR_EMBED))),
+    };
+
+    Ok((c, Node::Embed((lws, rws), path, encoding)))
+}
+
+// Partial tag with a macro call `name(args)` or `alias::name(args)`, the
+// parenthesis right after the name, `{{> partial (scope) }}` is a partial
+fn call(i: Input, lws: bool) -> Result<(Input, Node), nom::Err<Input>> {
+    let (i, (first, second)) = do_parse!(
+        i,
+        take_while!(ws)
+            >> first: identifier
+            >> second: opt!(preceded!(tag!("::"), identifier))
+            >> peek!(tag!("("))
+            >> ((first, second))
+    )?;
+    let (alias, name) = match second {
+        Some(name) => (Some(first), name),
+        None => (None, first),
+    };
+
+    let (i, args) = map_failure!(i, ERR_MACRO, call_args(i))?;
+    let (i, rws) = map_failure!(
+        i,
+        ERR_MACRO,
+        do_parse!(i, rws: opt!(tag!("~")) >> tag!("}}") >> (rws.is_some()))
+    )?;
+
+    Ok((i, Node::Call((lws, rws), alias, name, args)))
 }
 
 #[inline]
@@ -502,6 +747,20 @@ make_argument!(
     Result<(Input, Vec<Expr>), nom::Err<Input>>
 );
 
+fn eat_call_args(i: Input) -> Result<Vec<Expr>, nom::Err<Input>> {
+    let call = ["f", from_utf8(i.0).unwrap()].join("");
+    match parse_str::<ExprCall>(&call) {
+        Ok(call) => Ok(call.args.into_iter().collect()),
+        Err(_) => Err(nom::Err::Failure(error_position!(i, ERR_MACRO))),
+    }
+}
+
+make_argument!(
+    call_args,
+    eat_call_args,
+    Result<(Input, Vec<Expr>), nom::Err<Input>>
+);
+
 fn safe(i: Input, lws: bool) -> Result<(Input, Node), nom::Err<Input>> {
     let mut at = 0;
 
@@ -549,9 +808,108 @@ fn expr(i: Input, lws: bool) -> Result<(Input, Node), nom::Err<Input>> {
 
     let (_, s, _) = trim(s);
     try_eat_local!(c, s);
+    try_eat_trans!(c, s, (lws, rws));
+    try_eat_asset!(c, s, (lws, rws));
     eat_expr(s).map(|e| (c, Node::Expr((lws, rws), e)))
 }
 
+type Trans<'a> = (&'a str, Vec<(&'a str, Expr)>);
+
+// Key and named arguments of a translation, `None` when it's an expression
+// starting with `t`
+fn trans(i: Input) -> Option<Result<Trans, nom::Err<Input>>> {
+    let (_, i, _) = trim(i);
+    if !i.0.starts_with(b"\"") {
+        return None;
+    }
+
+    let fail = || nom::Err::Failure(error_position!(i, ERR_TRANS));
+    let end = match memchr(b'"', &i[1..]) {
+        Some(end) => end + 1,
+        None => return Some(Err(fail())),
+    };
+    let key = safe_utf8(&i[1..end]);
+    let list = safe_utf8(&i[end + 1..]);
+    if !list.is_empty() && !list.starts_with(|c: char| c.is_ascii_whitespace()) {
+        return Some(Err(fail()));
+    }
+
+    let mut args = vec![];
+    for (name, arg) in split_args(list.trim_start()) {
+        match parse_str::<Expr>(arg) {
+            Ok(e) if !name.is_empty() => args.push((name, e)),
+            _ => return Some(Err(fail())),
+        }
+    }
+
+    Some(Ok((key, args)))
+}
+
+// Path of an asset, `None` when it's an expression starting with `asset` or
+// `integrity`
+fn asset(i: Input<'_>) -> Option<Result<&str, nom::Err<Input<'_>>>> {
+    let (_, i, _) = trim(i);
+    if !i.0.starts_with(b"\"") {
+        return None;
+    }
+
+    Some(match memchr(b'"', &i[1..]) {
+        Some(end) if end + 2 == i.len() && 0 < end => Ok(safe_utf8(&i[1..end + 1])),
+        _ => Err(nom::Err::Failure(error_position!(i, ERR_ASSET))),
+    })
+}
+
+// Arguments `name=expr` separated by whitespace, a new one starts at an
+// identifier followed by `=` outside of brackets and strings
+fn split_args(list: &str) -> Vec<(&str, &str)> {
+    let bytes = list.as_bytes();
+    let mut starts = vec![];
+    let mut depth = 0usize;
+    let mut string = false;
+    let mut j = 0;
+    while j < bytes.len() {
+        match bytes[j] {
+            b'\\' if string => j += 1,
+            b'"' => string = !string,
+            b'(' | b'[' | b'{' if !string => depth += 1,
+            b')' | b']' | b'}' if !string => depth = depth.saturating_sub(1),
+            c if !string
+                && depth == 0
+                && (c.is_ascii_alphabetic() || c == b'_')
+                && (j == 0 || bytes[j - 1].is_ascii_whitespace()) =>
+            {
+                let name = bytes[j..]
+                    .iter()
+                    .position(|c| !c.is_ascii_alphanumeric() && *c != b'_')
+                    .map_or(bytes.len(), |n| j + n);
+                let eq = list[name..].trim_start();
+                if eq.starts_with('=') && !eq.starts_with("==") {
+                    starts.push(j);
+                }
+                j = name;
+                continue;
+            }
+            _ => (),
+        }
+        j += 1;
+    }
+
+    let mut args = vec![];
+    // text before the first name, an argument without name
+    let first = starts.first().copied().unwrap_or(list.len());
+    if 0 < first {
+        args.push(("", &list[..first]));
+    }
+    for (k, start) in starts.iter().enumerate() {
+        let end = starts.get(k + 1).copied().unwrap_or(list.len());
+        let arg = &list[*start..end];
+        let eq = arg.find('=').unwrap();
+        args.push((arg[..eq].trim(), arg[eq + 1..].trim()));
+    }
+
+    args
+}
+
 #[inline]
 fn eat_expr(i: Input) -> Result<Expr, nom::Err<Input>> {
     map_failure!(i, ERR_EXPR, parse_str::<Expr>(from_utf8(i.0).unwrap()))
@@ -1041,6 +1399,156 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_macro() {
+        let src = r#"{{#macro button(label, kind: &str)~}} {{ label }} {{/macro}}"#;
+        assert_eq!(
+            parse(src),
+            vec![Node::Macro(
+                ((false, true), WS),
+                "button",
+                vec![
+                    ("label", None),
+                    ("kind", Some(parse_str::<Type>("&str").unwrap()))
+                ],
+                vec![
+                    Node::Lit(" ", "", ""),
+                    Node::Expr(WS, parse_str::<Expr>("label").unwrap()),
+                    Node::Lit(" ", "", ""),
+                ]
+            )]
+        );
+
+        let src = r#"{{#macro empty()}}{{/macro}}{{#macro map(m: HashMap<u8, u8>)}}{{/macro}}"#;
+        assert_eq!(
+            parse(src),
+            vec![
+                Node::Macro((WS, WS), "empty", vec![], vec![]),
+                Node::Macro(
+                    (WS, WS),
+                    "map",
+                    vec![("m", Some(parse_str::<Type>("HashMap<u8, u8>").unwrap()))],
+                    vec![]
+                ),
+            ]
+        );
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_macro_panic() {
+        parse(r#"{{#macro a(1)}}{{/macro}}"#);
+    }
+
+    #[test]
+    fn test_import_call() {
+        let src = r#"{{~#import "forms.html" as forms }}{{> forms::input(name, "a, b") ~}}"#;
+        assert_eq!(
+            parse(src),
+            vec![
+                Node::Import((true, false), "forms.html", "forms"),
+                Node::Call(
+                    (false, true),
+                    Some("forms"),
+                    "input",
+                    vec![
+                        parse_str::<Expr>("name").unwrap(),
+                        parse_str::<Expr>("\"a, b\"").unwrap()
+                    ]
+                ),
+            ]
+        );
+        let src = r#"{{> button() }}{{> partial }}"#;
+        assert_eq!(
+            parse(src),
+            vec![
+                Node::Call(WS, None, "button", vec![]),
+                Node::Partial(WS, "partial", vec![]),
+            ]
+        );
+        let src = r#"{{> header (user) }}"#;
+        assert_eq!(
+            parse(src),
+            vec![Node::Partial(
+                WS,
+                "header",
+                vec![parse_str::<Expr>("(user)").unwrap()],
+            )]
+        );
+    }
+
+    #[test]
+    fn test_trans() {
+        let src = r#"{{ t "cart.items" count=items.len() name=f(a, "b c") ok=a == b ~}}"#;
+        assert_eq!(
+            parse(src),
+            vec![Node::Trans(
+                (false, true),
+                "cart.items",
+                vec![
+                    ("count", parse_str::<Expr>("items.len()").unwrap()),
+                    ("name", parse_str::<Expr>("f(a, \"b c\")").unwrap()),
+                    ("ok", parse_str::<Expr>("a == b").unwrap()),
+                ]
+            )]
+        );
+        let src = r#"{{ t "hello" }}{{ t }}{{ t + 1 }}"#;
+        assert_eq!(
+            parse(src),
+            vec![
+                Node::Trans(WS, "hello", vec![]),
+                Node::Expr(WS, parse_str::<Expr>("t").unwrap()),
+                Node::Expr(WS, parse_str::<Expr>("t + 1").unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_trans_panic() {
+        parse(r#"{{ t "hello" name }}"#);
+    }
+
+    #[test]
+    fn test_asset() {
+        let src = r#"{{ asset "css/app.css" }}{{~ integrity  "app.js" }}{{ asset }}"#;
+        assert_eq!(
+            parse(src),
+            vec![
+                Node::Asset(WS, Asset::Url, "css/app.css"),
+                Node::Asset((true, false), Asset::Integrity, "app.js"),
+                Node::Expr(WS, parse_str::<Expr>("asset").unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_asset_panic() {
+        parse(r#"{{ asset "app.css" "app.js" }}"#);
+    }
+
+    #[test]
+    fn test_embed() {
+        let src =
+            r#"{{> raw "icons/check.svg" }}{{~> raw "a.css" escape ~}}{{> raw "b.png" base64}}"#;
+        assert_eq!(
+            parse(src),
+            vec![
+                Node::Embed(WS, "icons/check.svg", Encoding::Verbatim),
+                Node::Embed((true, true), "a.css", Encoding::Escape),
+                Node::Embed(WS, "b.png", Encoding::Base64),
+            ]
+        );
+        assert_eq!(parse("{{> raw }}"), vec![Node::Partial(WS, "raw", vec![])]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_embed_panic() {
+        parse(r#"{{> raw "a.css" hex }}"#);
+    }
+
     #[test]
     fn test_raw() {
         let src = "{{R}}{{#some }}{{/some}}{{/R}}";
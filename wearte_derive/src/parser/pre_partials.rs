@@ -3,7 +3,7 @@ use memchr::memchr;
 use std::str::from_utf8;
 
 use crate::parser::raw;
-use crate::parser::{partial, Input, Node};
+use crate::parser::{import, partial, Input, Node};
 
 pub(crate) fn parse_partials(src: &str) -> Vec<Node> {
     match eat_partials(Input(src.as_bytes())) {
@@ -45,6 +45,17 @@ fn eat_partials(mut i: Input) -> Result<(Input, Vec<Node>), nom::Err<Input>> {
                                 Err(_) => i,
                             }
                         }
+                        b'#' => {
+                            let i = Input(&i[j + 3 + $t..]);
+                            match import(i, $ws) {
+                                Ok((i, n)) => {
+                                    nodes.push(n);
+                                    i
+                                }
+                                Err(nom::Err::Failure(err)) => break Err(nom::Err::Failure(err)),
+                                Err(_) => i,
+                            }
+                        }
                         b'R' => {
                             let i = Input(&i[j + 3 + $t..]);
                             match raw(i, $ws) {
@@ -93,4 +104,22 @@ mod tests {
         let src = r#"{{>}}"#;
         assert_eq!(parse_partials(src), vec![]);
     }
+
+    #[test]
+    fn test_imports() {
+        let src = r#"{{#import "forms" as forms}}{{#if a}}{{/if}}{{> forms::input(a) }}{{> b }}"#;
+        assert_eq!(
+            parse_partials(src),
+            vec![
+                Node::Import((false, false), "forms", "forms"),
+                Node::Call(
+                    (false, false),
+                    Some("forms"),
+                    "input",
+                    vec![syn::parse_str("a").unwrap()]
+                ),
+                Node::Partial((false, false), "b", vec![]),
+            ]
+        );
+    }
 }
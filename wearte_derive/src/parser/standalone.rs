@@ -0,0 +1,111 @@
+// Removes the indentation and line break of the lines that contain only a block tag,
+// Mustache-style. Block tags are helpers (`{{#each}}`, `{{else}}`, `{{/each}}`...) and
+// comments, tags with an explicit whitespace marker `~` or `+` are left to it.
+// Standalone comments are removed with their line, otherwise they would also eat
+// the whitespace around them. Raw blocks `{{R}}...{{/R}}` are not touched.
+// A macro defined in a single line `{{#macro ..}}..{{/macro}}` is a block tag too.
+pub(crate) fn strip_standalone(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut raw = false;
+    for line in src.split_inclusive('\n') {
+        let tag = line.trim_matches(|c| c == ' ' || c == '\t' || c == '\r' || c == '\n');
+        if !raw && is_standalone(tag) {
+            if !tag.starts_with("{{!") {
+                out.push_str(tag);
+            }
+            continue;
+        }
+
+        raw = in_raw(line, raw);
+        out.push_str(line);
+    }
+
+    out
+}
+
+fn is_standalone(tag: &str) -> bool {
+    if !(tag.starts_with("{{") && tag.ends_with("}}")) || tag.len() < 4 {
+        return false;
+    }
+    if tag.starts_with("{{#macro ") && tag.ends_with("{{/macro}}") {
+        return true;
+    }
+
+    let inner = &tag[2..tag.len() - 2];
+    if inner.contains("{{")
+        || inner.contains("}}")
+        || inner.starts_with(['~', '+'])
+        || inner.ends_with(['~', '+'])
+    {
+        return false;
+    }
+
+    match inner.trim_start().as_bytes().first() {
+        Some(b'#') | Some(b'!') => true,
+        // closing raw is always inside a raw block
+        Some(b'/') => inner.trim_start()[1..].trim() != "R",
+        _ => inner.trim_start().starts_with("else"),
+    }
+}
+
+// Whether the end of line is inside a raw block
+fn in_raw(line: &str, mut raw: bool) -> bool {
+    let mut rest = line;
+    while let Some(i) = rest.find("{{") {
+        let tag = rest[i + 2..].trim_start_matches('~');
+        if raw {
+            raw = !is_raw_tag(tag, "/R");
+        } else {
+            raw = is_raw_tag(tag, "R");
+        }
+        rest = &rest[i + 2..];
+    }
+
+    raw
+}
+
+// `R` or `/R` tag, not an expression starting with it like `{{Rows}}`
+pub(super) fn is_raw_tag(tag: &str, name: &str) -> bool {
+    tag.strip_prefix(name).is_some_and(|t| {
+        let t = t.trim_start();
+        t.starts_with("}}") || t.starts_with('~')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standalone() {
+        let src = "a\n  {{#each items}}\n  {{ key }}\n  {{else}}\n{{/each}}\nb";
+        assert_eq!(
+            strip_standalone(src),
+            "a\n{{#each items}}  {{ key }}\n{{else}}{{/each}}b"
+        );
+
+        let src = "{{! comment !}}\r\n{{#if a}} b\n{{~/if}}\n";
+        assert_eq!(strip_standalone(src), "{{#if a}} b\n{{~/if}}\n");
+
+        let src = "{{R}}\n{{#if a}}\n{{/R}}\n{{#if a}}\n";
+        assert_eq!(strip_standalone(src), "{{R}}\n{{#if a}}\n{{/R}}\n{{#if a}}");
+
+        let src = "{{Rows}}\n  {{#if a}}\n{{/if}}\n";
+        assert_eq!(strip_standalone(src), "{{Rows}}\n{{#if a}}{{/if}}");
+
+        let src = "{{R ~}}\n{{#if a}}\n{{~/R}}\n{{#if a}}\n";
+        assert_eq!(
+            strip_standalone(src),
+            "{{R ~}}\n{{#if a}}\n{{~/R}}\n{{#if a}}"
+        );
+
+        let src = "{{#if a}}{{/if}}\n{{ a }}\n";
+        assert_eq!(strip_standalone(src), src);
+
+        let src = "  {{#macro a()}}{{ b }}{{/macro}}\n{{> a() }}";
+        assert_eq!(
+            strip_standalone(src),
+            "{{#macro a()}}{{ b }}{{/macro}}{{> a() }}"
+        );
+    }
+}
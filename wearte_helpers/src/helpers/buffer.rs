@@ -0,0 +1,58 @@
+use std::fmt::{self, Write};
+
+/// Byte buffer the templates can render into without `fmt::Formatter`
+pub trait Buffer {
+    fn extend_from_slice(&mut self, src: &[u8]);
+    fn reserve(&mut self, additional: usize);
+
+    /// Called at the end of each loop iteration, where a streaming buffer
+    /// can send out what it has. An error stops the rendering, e.g. when the
+    /// consumer of the stream is gone
+    #[inline]
+    fn chunk_boundary(&mut self) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl Buffer for Vec<u8> {
+    #[inline]
+    fn extend_from_slice(&mut self, src: &[u8]) {
+        Vec::extend_from_slice(self, src)
+    }
+
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl Buffer for bytes::BytesMut {
+    #[inline]
+    fn extend_from_slice(&mut self, src: &[u8]) {
+        bytes::BytesMut::extend_from_slice(self, src)
+    }
+
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        bytes::BytesMut::reserve(self, additional)
+    }
+}
+
+/// `fmt::Write` adapter of a `Buffer`, for the values that need `Display`
+pub struct BufferWriter<'a, B: ?Sized>(&'a mut B);
+
+impl<'a, B: Buffer + ?Sized> BufferWriter<'a, B> {
+    #[inline]
+    pub fn new(buf: &'a mut B) -> Self {
+        BufferWriter(buf)
+    }
+}
+
+impl<'a, B: Buffer + ?Sized> Write for BufferWriter<'a, B> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
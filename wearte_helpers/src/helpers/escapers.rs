@@ -0,0 +1,267 @@
+use v_htmlescape::escape;
+
+use std::fmt::{self, Write};
+
+use super::buffer::{Buffer, BufferWriter};
+
+/// Escaping strategy applied to the output of a `{{ expression }}`
+///
+/// The escaper of a template is chosen by its extension, the `[escapers]` table
+/// of `wearte.toml` or the `escape` attribute. User defined escapers only need
+/// to be a type implementing this trait.
+pub trait Escaper {
+    fn escape<W: Write + ?Sized>(s: &str, writer: &mut W) -> fmt::Result;
+
+    /// Escapes straight into a byte buffer, used by `Template::render_into`
+    #[inline]
+    fn escape_bytes<B: Buffer + ?Sized>(s: &str, buf: &mut B) -> fmt::Result {
+        Self::escape(s, &mut BufferWriter::new(buf))
+    }
+
+    /// MIME types of the nested templates written as is, a leading `/` or `+`
+    /// matches a suffix. Empty, the default, accepts no nested template. Checked
+    /// at compile time for the fields of a derived template
+    const MIMES: &'static [&'static str] = &[];
+
+    /// Whether the output of a nested template with the MIME type `mime` can be
+    /// written as is, by `MIMES` by default
+    #[inline]
+    fn accepts(mime: &str) -> bool {
+        accepts_mime(Self::MIMES, mime)
+    }
+
+    /// Whether `Html` values are written as is, only by the HTML escaper
+    #[inline]
+    fn trusts_html() -> bool {
+        false
+    }
+}
+
+/// HTML escaper, default for `html`, `htm`, `xml`, `hbs`, `handlebars` and `mustache`
+pub struct Html;
+
+impl Escaper for Html {
+    #[inline]
+    fn escape<W: Write + ?Sized>(s: &str, writer: &mut W) -> fmt::Result {
+        write!(writer, "{}", escape(s))
+    }
+
+    // same output as `v_htmlescape`
+    #[inline]
+    fn escape_bytes<B: Buffer + ?Sized>(s: &str, buf: &mut B) -> fmt::Result {
+        escape_bytes_by(s, buf, |b| match b {
+            b'<' => Some("&lt;"),
+            b'>' => Some("&gt;"),
+            b'&' => Some("&amp;"),
+            b'"' => Some("&quot;"),
+            b'\'' => Some("&#x27;"),
+            b'/' => Some("&#x2f;"),
+            _ => None,
+        })
+    }
+
+    // markup and inline SVG
+    const MIMES: &'static [&'static str] = &["text/html", "image/svg+xml"];
+
+    fn trusts_html() -> bool {
+        true
+    }
+}
+
+/// Escaper for the content of JSON strings
+pub struct Json;
+
+impl Escaper for Json {
+    fn escape<W: Write + ?Sized>(s: &str, writer: &mut W) -> fmt::Result {
+        escape_by(s, writer, json)
+    }
+
+    fn escape_bytes<B: Buffer + ?Sized>(s: &str, buf: &mut B) -> fmt::Result {
+        escape_bytes_by(s, buf, json)
+    }
+
+    const MIMES: &'static [&'static str] = &["application/json", "+json"];
+}
+
+fn json(b: u8) -> Option<&'static str> {
+    match b {
+        b'"' => Some("\\\""),
+        b'\\' => Some("\\\\"),
+        b'\n' => Some("\\n"),
+        b'\r' => Some("\\r"),
+        b'\t' => Some("\\t"),
+        0x08 => Some("\\b"),
+        0x0c => Some("\\f"),
+        0x00..=0x1f => Some(CONTROL[b as usize]),
+        _ => None,
+    }
+}
+
+/// Escaper for XML text and attribute values
+pub struct Xml;
+
+impl Escaper for Xml {
+    fn escape<W: Write + ?Sized>(s: &str, writer: &mut W) -> fmt::Result {
+        escape_by(s, writer, xml)
+    }
+
+    fn escape_bytes<B: Buffer + ?Sized>(s: &str, buf: &mut B) -> fmt::Result {
+        escape_bytes_by(s, buf, xml)
+    }
+
+    const MIMES: &'static [&'static str] = &["/xml", "+xml"];
+}
+
+fn xml(b: u8) -> Option<&'static str> {
+    match b {
+        b'<' => Some("&lt;"),
+        b'>' => Some("&gt;"),
+        b'&' => Some("&amp;"),
+        b'"' => Some("&quot;"),
+        b'\'' => Some("&apos;"),
+        _ => None,
+    }
+}
+
+/// Whether the essence of `mime`, without parameters, matches one of `mimes`
+/// as in `Escaper::MIMES`. A `const fn` for the checks of the generated code
+#[doc(hidden)]
+pub const fn accepts_mime(mimes: &[&str], mime: &str) -> bool {
+    let m = mime.as_bytes();
+    let mut end = 0;
+    while end < m.len() && m[end] != b';' {
+        end += 1;
+    }
+    while end > 0 && m[end - 1].is_ascii_whitespace() {
+        end -= 1;
+    }
+    let mut start = 0;
+    while start < end && m[start].is_ascii_whitespace() {
+        start += 1;
+    }
+
+    let mut i = 0;
+    while i < mimes.len() {
+        let p = mimes[i].as_bytes();
+        let suffix = !p.is_empty() && (p[0] == b'/' || p[0] == b'+');
+        if (!suffix && end - start == p.len()) || (suffix && end - start > p.len()) {
+            let at = end - p.len();
+            let mut j = 0;
+            while j < p.len() && m[at + j] == p[j] {
+                j += 1;
+            }
+            if j == p.len() {
+                return true;
+            }
+        }
+        i += 1;
+    }
+
+    false
+}
+
+// All escaped characters are ascii, so slicing between them is always at char boundary
+#[inline]
+fn escape_by<W, F>(s: &str, writer: &mut W, f: F) -> fmt::Result
+where
+    W: Write + ?Sized,
+    F: Fn(u8) -> Option<&'static str>,
+{
+    let mut last = 0;
+    for (i, b) in s.bytes().enumerate() {
+        if let Some(escaped) = f(b) {
+            if last < i {
+                writer.write_str(&s[last..i])?;
+            }
+            writer.write_str(escaped)?;
+            last = i + 1;
+        }
+    }
+
+    if last < s.len() {
+        writer.write_str(&s[last..])?;
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn escape_bytes_by<B, F>(s: &str, buf: &mut B, f: F) -> fmt::Result
+where
+    B: Buffer + ?Sized,
+    F: Fn(u8) -> Option<&'static str>,
+{
+    let bytes = s.as_bytes();
+    let mut last = 0;
+    for (i, b) in bytes.iter().enumerate() {
+        if let Some(escaped) = f(*b) {
+            buf.extend_from_slice(&bytes[last..i]);
+            buf.extend_from_slice(escaped.as_bytes());
+            last = i + 1;
+        }
+    }
+    buf.extend_from_slice(&bytes[last..]);
+
+    Ok(())
+}
+
+#[rustfmt::skip]
+static CONTROL: [&str; 32] = [
+    "\\u0000", "\\u0001", "\\u0002", "\\u0003", "\\u0004", "\\u0005", "\\u0006", "\\u0007",
+    "\\u0008", "\\u0009", "\\u000a", "\\u000b", "\\u000c", "\\u000d", "\\u000e", "\\u000f",
+    "\\u0010", "\\u0011", "\\u0012", "\\u0013", "\\u0014", "\\u0015", "\\u0016", "\\u0017",
+    "\\u0018", "\\u0019", "\\u001a", "\\u001b", "\\u001c", "\\u001d", "\\u001e", "\\u001f",
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bytes<E: Escaper>(s: &str) -> String {
+        let mut buf = vec![];
+        E::escape_bytes(s, &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_accepts() {
+        assert!(Html::accepts("text/html; charset=utf-8"));
+        assert!(Html::accepts("image/svg+xml"));
+        assert!(!Html::accepts("text/plain"));
+        assert!(Json::accepts("application/ld+json"));
+        assert!(!Json::accepts("text/html"));
+        assert!(Xml::accepts("application/atom+xml"));
+        assert!(Xml::accepts("text/xml"));
+        assert!(!Xml::accepts("text/plain"));
+        assert!(Json::accepts(" application/json ; charset=utf-8"));
+        assert!(!Json::accepts("+json"));
+        assert!(!accepts_mime(&[], "text/plain"));
+        assert!(!accepts_mime(&["text/html"], "text/htm"));
+    }
+
+    #[test]
+    fn test_escape_bytes() {
+        let s = "<a href='/b'>\"&\"</a>\n";
+        for (e, b) in &[
+            (escape(s).to_string(), bytes::<Html>(s)),
+            (
+                {
+                    let mut buf = String::new();
+                    Json::escape(s, &mut buf).unwrap();
+                    buf
+                },
+                bytes::<Json>(s),
+            ),
+            (
+                {
+                    let mut buf = String::new();
+                    Xml::escape(s, &mut buf).unwrap();
+                    buf
+                },
+                bytes::<Xml>(s),
+            ),
+        ] {
+            assert_eq!(e, b);
+        }
+    }
+}
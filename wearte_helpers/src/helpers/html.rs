@@ -0,0 +1,135 @@
+use std::fmt::{self, Display, Write};
+
+use super::buffer::Buffer;
+use super::escapers::Escaper;
+use super::render::Render;
+
+/// Markup that is safe to output as is
+///
+/// Escaped expressions `{{ }}` of HTML templates write it without escaping, so
+/// helpers returning HTML fragments don't need the triple-stash `{{{ }}}`. Other
+/// escapers, e.g. JSON, escape it as any other string.
+///
+/// It can only be built from escaped parts with [`html!`](../macro.html.html),
+/// from a template with `Template::as_html` or explicitly with
+/// [`Html::trusted`](#method.trusted).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Html<T = String>(T);
+
+impl<T: AsRef<str>> Html<T> {
+    /// Marks `t` as safe markup, it will never be escaped
+    #[inline]
+    pub fn trusted(t: T) -> Html<T> {
+        Html(t)
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.0.as_ref()
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl Html<String> {
+    /// HTML escapes `s`
+    #[inline]
+    pub fn escape(s: &str) -> Html<String> {
+        Html(v_htmlescape::escape(s).to_string())
+    }
+}
+
+impl<T: AsRef<str>> Render for Html<T> {
+    #[inline]
+    fn render<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        if E::trusts_html() {
+            writer.write_str(self.as_str())
+        } else {
+            E::escape(self.as_str(), writer)
+        }
+    }
+
+    #[inline]
+    fn render_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        if E::trusts_html() {
+            buf.extend_from_slice(self.as_str().as_bytes());
+            Ok(())
+        } else {
+            E::escape_bytes(self.as_str(), buf)
+        }
+    }
+}
+
+impl<T: AsRef<str>> Display for Html<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Builds an [`Html`](helpers/struct.Html.html) from string literals, output
+/// as is, and expressions, HTML escaped
+///
+/// ```
+/// use wearte_helpers::html;
+///
+/// let name = "<script>";
+/// let icon = html!("<i class=\"", name, "\"></i>");
+/// assert_eq!(icon.as_str(), "<i class=\"&lt;script&gt;\"></i>");
+/// ```
+#[macro_export]
+macro_rules! html {
+    ($($t:tt)*) => {{
+        let mut _buf = ::std::string::String::new();
+        $crate::__html!(_buf; $($t)*);
+        $crate::helpers::Html::trusted(_buf)
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __html {
+    ($buf:ident;) => {};
+    ($buf:ident; $l:literal $(, $($t:tt)*)?) => {
+        $buf.push_str(concat!($l));
+        $crate::__html!($buf; $($($t)*)?);
+    };
+    ($buf:ident; $e:expr $(, $($t:tt)*)?) => {
+        {
+            #[allow(unused_imports)]
+            use $crate::helpers::{ViaDisplay as _, ViaRender as _};
+            (&$crate::helpers::Wrap(&($e)))
+                .render_escaped::<$crate::helpers::escapers::Html, _>(&mut $buf)
+                .unwrap();
+        }
+        $crate::__html!($buf; $($($t)*)?);
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_html() {
+        let a = "<a>";
+        let b = html!("<b>", a, 1, "</b>", Html::trusted("<br>"));
+        assert_eq!(b.as_str(), "<b>&lt;a&gt;1</b><br>");
+        assert_eq!(html!().as_str(), "");
+
+        let mut buf = String::new();
+        b.render::<crate::helpers::escapers::Html, _>(&mut buf)
+            .unwrap();
+        assert_eq!(buf, "<b>&lt;a&gt;1</b><br>");
+        assert_eq!(Html::escape("&").to_string(), "&amp;");
+
+        let mut buf = String::new();
+        Html::trusted("<b a=\"1\">")
+            .render::<crate::helpers::escapers::Json, _>(&mut buf)
+            .unwrap();
+        assert_eq!(buf, "<b a=\\\"1\\\">");
+    }
+}
@@ -0,0 +1,90 @@
+/// Number selecting the plural form of a translated message, the `count` argument
+///
+/// Implemented for the primitive integers, negative numbers take the form of
+/// their absolute value.
+pub trait PluralCount {
+    fn plural_count(&self) -> u64;
+}
+
+macro_rules! impl_unsigned {
+    ($($t:ty)+) => ($(
+        impl PluralCount for $t {
+            #[inline]
+            fn plural_count(&self) -> u64 {
+                *self as u64
+            }
+        }
+    )+)
+}
+
+macro_rules! impl_signed {
+    ($($t:ty)+) => ($(
+        impl PluralCount for $t {
+            #[inline]
+            fn plural_count(&self) -> u64 {
+                self.unsigned_abs() as u64
+            }
+        }
+    )+)
+}
+
+impl_unsigned!(u8 u16 u32 u64 u128 usize);
+impl_signed!(i8 i16 i32 i64 i128 isize);
+
+impl<T: PluralCount + ?Sized> PluralCount for &T {
+    #[inline]
+    fn plural_count(&self) -> u64 {
+        (**self).plural_count()
+    }
+}
+
+/// Index of the catalog for `locale` in `available`
+///
+/// Tags are compared ignoring case, `_` and the encoding, e.g. `de_AT.UTF-8` is
+/// `de-AT`. Without an exact match the language alone is compared, `de-AT`
+/// takes `de` and `pt-BR` takes `pt-PT`.
+#[doc(hidden)]
+pub fn negotiate(locale: &str, available: &[&str]) -> Option<usize> {
+    let locale = locale.split(['.', '@']).next().unwrap_or("");
+    available.iter().position(|a| same(a, locale)).or_else(|| {
+        available
+            .iter()
+            .position(|a| same(language(a), language(locale)))
+    })
+}
+
+fn same(a: &str, b: &str) -> bool {
+    let sep = |c: u8| c == b'_' || c == b'-';
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .all(|(a, b)| a.eq_ignore_ascii_case(&b) || sep(a) && sep(b))
+}
+
+fn language(tag: &str) -> &str {
+    tag.split(['-', '_']).next().unwrap_or("")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let available = ["de", "en-US", "pt-PT"];
+        assert_eq!(negotiate("de", &available), Some(0));
+        assert_eq!(negotiate("DE_at.UTF-8", &available), Some(0));
+        assert_eq!(negotiate("en_us", &available), Some(1));
+        assert_eq!(negotiate("en-GB", &available), Some(1));
+        assert_eq!(negotiate("pt-BR", &available), Some(2));
+        assert_eq!(negotiate("fr", &available), None);
+        assert_eq!(negotiate("", &available), None);
+    }
+
+    #[test]
+    fn test_plural_count() {
+        assert_eq!(3usize.plural_count(), 3);
+        assert_eq!((-2i32).plural_count(), 2);
+        assert_eq!((&&7u8).plural_count(), 7);
+    }
+}
@@ -1,10 +1,14 @@
-cfg_if! {
-    if #[cfg(wearte_nightly)] {
-        #[path = "markup-night.rs"]
-        mod markup;
-        pub use markup::MarkupAsStr;
-    } else {
-        mod markup;
-        pub use markup::MarkupAsStr;
-    }
-}
+mod buffer;
+pub mod escapers;
+mod html;
+mod i18n;
+mod render;
+mod size_hint;
+mod writer;
+
+pub use self::buffer::{Buffer, BufferWriter};
+pub use self::html::Html;
+pub use self::i18n::{negotiate, PluralCount};
+pub use self::render::{AsStr, Render, ViaDisplay, ViaRender, ViaStr, Wrap};
+pub use self::size_hint::SizeHint;
+pub use self::writer::EscapeWriter;
@@ -0,0 +1,321 @@
+use std::borrow::{Cow, ToOwned};
+use std::fmt::{self, Display, Write};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use super::buffer::{Buffer, BufferWriter};
+use super::escapers::Escaper;
+use super::writer::EscapeWriter;
+
+/// Output of a `{{ expression }}`
+///
+/// Strings and characters are passed through the template escaper, numbers and
+/// booleans are written as is. Implement it for your own types to output them
+/// in escaped expressions:
+///
+/// ```
+/// use std::fmt::{self, Write};
+/// use wearte_helpers::helpers::{escapers::Escaper, Render};
+///
+/// struct Name(String);
+///
+/// impl Render for Name {
+///     fn render<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+///         writer.write_str("@")?;
+///         E::escape(&self.0, writer)
+///     }
+/// }
+/// ```
+pub trait Render {
+    fn render<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result;
+
+    /// Renders straight into a byte buffer, used by `Template::render_into`
+    #[inline]
+    fn render_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        self.render::<E, _>(&mut BufferWriter::new(buf))
+    }
+}
+
+impl Render for str {
+    #[inline]
+    fn render<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        E::escape(self, writer)
+    }
+
+    #[inline]
+    fn render_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        E::escape_bytes(self, buf)
+    }
+}
+
+impl Render for String {
+    #[inline]
+    fn render<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        E::escape(self, writer)
+    }
+
+    #[inline]
+    fn render_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        E::escape_bytes(self, buf)
+    }
+}
+
+impl Render for char {
+    #[inline]
+    fn render<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        E::escape(self.encode_utf8(&mut [0; 4]), writer)
+    }
+
+    #[inline]
+    fn render_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        E::escape_bytes(self.encode_utf8(&mut [0; 4]), buf)
+    }
+}
+
+macro_rules! impl_safe {
+    ($($t:ty)+) => ($(
+        impl Render for $t {
+            #[inline]
+            fn render<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+                write!(writer, "{}", self)
+            }
+        }
+    )+)
+}
+
+#[rustfmt::skip]
+impl_safe!(
+    bool
+    usize u8 u16 u32 u64 u128
+    isize i8 i16 i32 i64 i128
+    f32 f64
+);
+
+macro_rules! impl_deref {
+    ($($t:ty)+) => ($(
+        impl<T: Render + ?Sized> Render for $t {
+            #[inline]
+            fn render<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+                (**self).render::<E, W>(writer)
+            }
+
+            #[inline]
+            fn render_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+                (**self).render_bytes::<E, B>(buf)
+            }
+        }
+    )+)
+}
+
+impl_deref!(&T &mut T Box<T> Rc<T> Arc<T>);
+
+impl<'a, T: Render + ToOwned + ?Sized> Render for Cow<'a, T> {
+    #[inline]
+    fn render<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        (**self).render::<E, W>(writer)
+    }
+
+    #[inline]
+    fn render_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        (**self).render_bytes::<E, B>(buf)
+    }
+}
+
+/// `None` outputs nothing
+impl<T: Render> Render for Option<T> {
+    #[inline]
+    fn render<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        match self {
+            Some(t) => t.render::<E, W>(writer),
+            None => Ok(()),
+        }
+    }
+
+    #[inline]
+    fn render_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        match self {
+            Some(t) => t.render_bytes::<E, B>(buf),
+            None => Ok(()),
+        }
+    }
+}
+
+// Expression dispatch used by the generated code. `Render` types are output
+// through their impl, any other `Display` type is escaped while formatted.
+// Generated code calls `(&Wrap(&expr)).render_escaped::<E, _>(_fmt)`, method
+// resolution picks `ViaRender` before autoref reaches `ViaDisplay`.
+// Unescaped expressions of the byte output use `ViaStr` in the same way, so
+// strings are copied without `fmt`.
+#[doc(hidden)]
+pub struct Wrap<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait ViaRender {
+    fn render_escaped<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result;
+    fn render_escaped_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result;
+}
+
+impl<'a, T: Render + ?Sized> ViaRender for Wrap<'a, T> {
+    #[inline]
+    fn render_escaped<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.0.render::<E, W>(writer)
+    }
+
+    #[inline]
+    fn render_escaped_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        self.0.render_bytes::<E, B>(buf)
+    }
+}
+
+#[doc(hidden)]
+pub trait ViaDisplay {
+    fn render_escaped<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result;
+    fn render_escaped_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result;
+    fn render_raw_bytes<B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result;
+}
+
+impl<'a, T: Display + ?Sized> ViaDisplay for &Wrap<'a, T> {
+    #[inline]
+    fn render_escaped<E: Escaper, W: Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        write!(EscapeWriter::<E, W>::new(writer), "{}", self.0)
+    }
+
+    #[inline]
+    fn render_escaped_bytes<E: Escaper, B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        let mut writer = BufferWriter::new(buf);
+        write!(EscapeWriter::<E, _>::new(&mut writer), "{}", self.0)
+    }
+
+    #[inline]
+    fn render_raw_bytes<B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        write!(BufferWriter::new(buf), "{}", self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait ViaStr {
+    fn render_raw_bytes<B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result;
+}
+
+impl<'a, T: AsStr + ?Sized> ViaStr for Wrap<'a, T> {
+    #[inline]
+    fn render_raw_bytes<B: Buffer + ?Sized>(&self, buf: &mut B) -> fmt::Result {
+        buf.extend_from_slice(self.0.as_str().as_bytes());
+        Ok(())
+    }
+}
+
+// String types whose `Display` is the string itself
+#[doc(hidden)]
+pub trait AsStr {
+    fn as_str(&self) -> &str;
+}
+
+macro_rules! impl_as_str {
+    ($($t:ty)+) => ($(
+        impl AsStr for $t {
+            #[inline]
+            fn as_str(&self) -> &str {
+                self
+            }
+        }
+    )+)
+}
+
+impl_as_str!(str String Box<str> Rc<str> Arc<str>);
+
+impl<'a> AsStr for Cow<'a, str> {
+    #[inline]
+    fn as_str(&self) -> &str {
+        self
+    }
+}
+
+impl<T: AsStr + ?Sized> AsStr for &T {
+    #[inline]
+    fn as_str(&self) -> &str {
+        (**self).as_str()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::helpers::escapers::Html;
+
+    fn render<T: Render + ?Sized>(t: &T) -> String {
+        let mut buf = String::new();
+        t.render::<Html, _>(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(render("<a>"), "&lt;a&gt;");
+        assert_eq!(render(&String::from("&")), "&amp;");
+        assert_eq!(render(&'<'), "&lt;");
+        assert_eq!(render(&1.5f64), "1.5");
+        assert_eq!(render(&-1i128), "-1");
+        assert_eq!(render(&true), "true");
+        assert_eq!(render(&Cow::Borrowed("<")), "&lt;");
+        assert_eq!(render(&Arc::<str>::from("<")), "&lt;");
+        assert_eq!(render(&Rc::new(Box::new("<"))), "&lt;");
+        assert_eq!(render(&Some("<")), "&lt;");
+        assert_eq!(render(&None::<&str>), "");
+    }
+
+    struct Tag;
+
+    impl Display for Tag {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("<")?;
+            f.write_str("b>")
+        }
+    }
+
+    // same call as the generated code
+    macro_rules! dispatch {
+        ($buf:ident, $e:expr) => {
+            (&Wrap(&($e))).render_escaped::<Html, _>(&mut $buf).unwrap()
+        };
+        (bytes $buf:ident, $e:expr) => {
+            (&Wrap(&($e)))
+                .render_escaped_bytes::<Html, _>(&mut $buf)
+                .unwrap()
+        };
+        (raw $buf:ident, $e:expr) => {
+            (&Wrap(&($e))).render_raw_bytes(&mut $buf).unwrap()
+        };
+    }
+
+    fn render_bytes<T: Render + ?Sized>(t: &T) -> String {
+        let mut buf = vec![];
+        t.render_bytes::<Html, _>(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_render_bytes() {
+        assert_eq!(render_bytes("<a>"), "&lt;a&gt;");
+        assert_eq!(render_bytes(&Arc::<str>::from("<")), "&lt;");
+        assert_eq!(render_bytes(&Some(&&'&')), "&amp;");
+        assert_eq!(render_bytes(&1.5f64), "1.5");
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let mut buf = String::new();
+        dispatch!(buf, 1u8);
+        dispatch!(buf, "&");
+        dispatch!(buf, Tag);
+        assert_eq!(buf, "1&amp;&lt;b&gt;");
+
+        let mut buf = vec![];
+        dispatch!(bytes buf, 1u8);
+        dispatch!(bytes buf, "&");
+        dispatch!(bytes buf, Tag);
+        dispatch!(raw buf, "&");
+        dispatch!(raw buf, Tag);
+        assert_eq!(buf, b"1&amp;&lt;b&gt;&<b>");
+    }
+}
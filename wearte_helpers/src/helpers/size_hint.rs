@@ -0,0 +1,49 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Output size estimate learned from the last renders of a template type
+///
+/// Starts at the compile-time estimate, grows at once to any bigger output and
+/// decays slowly to smaller ones, so a single small render doesn't cause
+/// reallocations on the next ones.
+#[derive(Debug)]
+pub struct SizeHint(AtomicUsize);
+
+impl SizeHint {
+    pub const fn new(hint: usize) -> SizeHint {
+        SizeHint(AtomicUsize::new(hint))
+    }
+
+    #[inline]
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Records the length of a rendered output
+    #[inline]
+    pub fn record(&self, len: usize) {
+        let last = self.get();
+        let hint = if last < len {
+            len
+        } else {
+            last - (last - len) / 4
+        };
+        self.0.store(hint, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_size_hint() {
+        let hint = SizeHint::new(100);
+        assert_eq!(hint.get(), 100);
+        hint.record(200);
+        assert_eq!(hint.get(), 200);
+        hint.record(100);
+        assert_eq!(hint.get(), 175);
+        hint.record(175);
+        assert_eq!(hint.get(), 175);
+    }
+}
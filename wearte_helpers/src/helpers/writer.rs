@@ -0,0 +1,57 @@
+use std::fmt::{self, Write};
+use std::marker::PhantomData;
+
+use super::escapers::Escaper;
+
+/// `fmt::Write` adapter escaping every chunk written through it
+///
+/// Lets any `Display` value be escaped while it is formatted, without
+/// an intermediate `String`.
+///
+/// ```
+/// use std::fmt::Write;
+/// use wearte_helpers::helpers::{escapers::Html, EscapeWriter};
+///
+/// let mut buf = String::new();
+/// write!(EscapeWriter::<Html, _>::new(&mut buf), "{}<{}", 1, 2).unwrap();
+/// assert_eq!(buf, "1&lt;2");
+/// ```
+pub struct EscapeWriter<'a, E, W: ?Sized> {
+    inner: &'a mut W,
+    _escaper: PhantomData<E>,
+}
+
+impl<'a, E: Escaper, W: Write + ?Sized> EscapeWriter<'a, E, W> {
+    #[inline]
+    pub fn new(inner: &'a mut W) -> Self {
+        EscapeWriter {
+            inner,
+            _escaper: PhantomData,
+        }
+    }
+}
+
+impl<'a, E: Escaper, W: Write + ?Sized> Write for EscapeWriter<'a, E, W> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        E::escape(s, self.inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::helpers::escapers::{Html, Json};
+
+    #[test]
+    fn test_escape_writer() {
+        let mut buf = String::new();
+        let (a, b) = ("<a>", "b");
+        write!(EscapeWriter::<Html, _>::new(&mut buf), "{}&{:?}", a, b).unwrap();
+        assert_eq!(buf, "&lt;a&gt;&amp;&quot;b&quot;");
+
+        let mut buf = String::new();
+        write!(EscapeWriter::<Json, _>::new(&mut buf), "{:?}", 'a').unwrap();
+        assert_eq!(buf, "'a'");
+    }
+}
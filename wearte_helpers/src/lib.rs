@@ -1,8 +1,3 @@
-#![cfg_attr(wearte_nightly, feature(specialization))]
-
-#[macro_use]
-extern crate cfg_if;
-
 pub use std::fmt::Error;
 pub type Result<I> = ::std::result::Result<I, Error>;
 pub mod helpers;